@@ -0,0 +1,272 @@
+//! Command line tool to analyze, synthesize and transform speech with WORLD.
+
+mod params;
+mod transform;
+mod wav;
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use world::options::{F0Estimator, Preset};
+use world::signal_analyzer::SignalAnalyzerBuilder;
+use world::synthesis::synthesis_with_length;
+
+use crate::params::Parameters;
+
+#[derive(Parser)]
+#[command(version, about = "Analyze, synthesize and transform speech with WORLD")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Analyze a WAV file into f0, spectral envelope and aperiodicity
+    Analyze {
+        input: PathBuf,
+        /// Directory to write the parameters to
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        analysis: AnalysisArgs,
+    },
+    /// Synthesize a WAV file from the parameters written by `analyze`
+    Synth {
+        /// Directory of the parameters
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        transform: TransformArgs,
+        #[command(flatten)]
+        synthesis: SynthesisArgs,
+    },
+    /// Analyze, transform and synthesize a WAV file
+    Resynth {
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        analysis: AnalysisArgs,
+        #[command(flatten)]
+        transform: TransformArgs,
+        #[command(flatten)]
+        synthesis: SynthesisArgs,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum F0EstimatorArg {
+    Harvest,
+    Dio,
+}
+
+impl From<F0EstimatorArg> for F0Estimator {
+    fn from(value: F0EstimatorArg) -> Self {
+        match value {
+            F0EstimatorArg::Harvest => F0Estimator::Harvest,
+            F0EstimatorArg::Dio => F0Estimator::Dio,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PresetArg {
+    Speech,
+    Singing,
+    LowVoice,
+}
+
+impl From<PresetArg> for Preset {
+    fn from(value: PresetArg) -> Self {
+        match value {
+            PresetArg::Speech => Preset::Speech,
+            PresetArg::Singing => Preset::Singing,
+            PresetArg::LowVoice => Preset::LowVoice,
+        }
+    }
+}
+
+#[derive(Args)]
+struct AnalysisArgs {
+    #[arg(long, value_enum, default_value = "harvest")]
+    f0_estimator: F0EstimatorArg,
+    /// Set of the f0 range and the D4C threshold, which the options below override
+    #[arg(long, value_enum)]
+    preset: Option<PresetArg>,
+    /// Lower bound of f0 [Hz]
+    #[arg(long)]
+    f0_floor: Option<f64>,
+    /// Upper bound of f0 [Hz]
+    #[arg(long)]
+    f0_ceil: Option<f64>,
+    /// Frame period [ms]
+    #[arg(long)]
+    frame_period: Option<f64>,
+    /// Threshold of D4C to determine the unvoiced frames
+    #[arg(long)]
+    d4c_threshold: Option<f64>,
+}
+
+#[derive(Args)]
+struct TransformArgs {
+    /// Pitch shift in semitones, e.g. +3 or -2.5
+    #[arg(long, default_value_t = 0., allow_hyphen_values = true)]
+    pitch: f64,
+    /// Ratio to move the formants by, e.g. 1.1 for higher formants
+    #[arg(long, default_value_t = 1.)]
+    formant: f64,
+    /// Speed ratio, e.g. 0.9 for 10% slower
+    #[arg(long, default_value_t = 1.)]
+    speed: f64,
+}
+
+#[derive(Args)]
+struct SynthesisArgs {
+    /// Seed of the noise for the aperiodic components
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Write 32 bit float samples instead of 16 bit integers
+    #[arg(long)]
+    float: bool,
+}
+
+fn analyze(input: &Path, args: &AnalysisArgs) -> Result<Parameters, Box<dyn Error>> {
+    let (signal, fs) = wav::read(input).map_err(|error| format!("{}: {error}", input.display()))?;
+    let mut builder = SignalAnalyzerBuilder::try_new(fs)?;
+    builder.set_f0_estimator(args.f0_estimator.into())?;
+    if let Some(preset) = args.preset {
+        builder.apply_preset(preset.into())?;
+    }
+    if let Some(f0_floor) = args.f0_floor {
+        builder.set_f0_floor(f0_floor)?;
+    }
+    if let Some(f0_ceil) = args.f0_ceil {
+        builder.set_f0_ceil(f0_ceil);
+    }
+    if let Some(frame_period) = args.frame_period {
+        builder.set_frame_period(frame_period);
+    }
+    if let Some(threshold) = args.d4c_threshold {
+        builder.d4c_option_mut().set_threshold(threshold);
+    }
+    let analyzer = builder.try_build(signal.into_boxed_slice())?;
+    analyzer.calc_all();
+    Ok(Parameters::from_result(&analyzer.into_result())?)
+}
+
+fn transform(parameters: &mut Parameters, args: &TransformArgs) -> Result<(), Box<dyn Error>> {
+    if !(args.pitch.is_finite() && args.formant.is_finite() && args.formant > 0. && args.speed.is_finite() && args.speed > 0.) {
+        return Err("pitch must be finite, and formant and speed must be positive".into());
+    }
+    transform::shift_pitch(&mut parameters.synthesis.f0, args.pitch);
+    if args.formant != 1. {
+        transform::shift_formant(&mut parameters.synthesis.spectrogram, args.formant);
+    }
+    if args.speed != 1. {
+        transform::change_speed(parameters, args.speed);
+    }
+    Ok(())
+}
+
+/// Synthesize the signal of the length of the analyzed signal, or of the length changed by the speed.
+fn synthesize(output: &Path, parameters: &Parameters, args: &SynthesisArgs) -> Result<(), Box<dyn Error>> {
+    let Parameters { synthesis, samples } = parameters;
+    let signal = synthesis_with_length(
+        &synthesis.f0,
+        &synthesis.spectrogram,
+        &synthesis.aperiodicity,
+        None,
+        synthesis.frame_period,
+        synthesis.fs,
+        args.seed,
+        None,
+        *samples,
+    )?;
+    wav::write(output, &signal, synthesis.fs, args.float).map_err(|error| format!("{}: {error}", output.display()).into())
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    match cli.command {
+        Command::Analyze { input, output, analysis } => analyze(&input, &analysis)?
+            .write(&output)
+            .map_err(|error| format!("{}: {error}", output.display()).into()),
+        Command::Synth {
+            input,
+            output,
+            transform: transform_args,
+            synthesis,
+        } => {
+            let mut parameters = Parameters::read(&input).map_err(|error| format!("{}: {error}", input.display()))?;
+            transform(&mut parameters, &transform_args)?;
+            synthesize(&output, &parameters, &synthesis)
+        }
+        Command::Resynth {
+            input,
+            output,
+            analysis,
+            transform: transform_args,
+            synthesis,
+        } => {
+            let mut parameters = analyze(&input, &analysis)?;
+            transform(&mut parameters, &transform_args)?;
+            synthesize(&output, &parameters, &synthesis)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use world::spectrogram_like::SpectrogramLike;
+    use world::synthesis::SynthesisParameters;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let cli = Cli::try_parse_from(["world", "resynth", "in.wav", "-o", "out.wav", "--f0-estimator", "dio", "--pitch", "-2.5"]).unwrap();
+        let Command::Resynth { analysis, transform, .. } = cli.command else {
+            panic!("resynth is expected");
+        };
+        assert!(matches!(analysis.f0_estimator, F0EstimatorArg::Dio));
+        assert_eq!((transform.pitch, transform.formant, transform.speed), (-2.5, 1., 1.));
+        assert!(Cli::try_parse_from(["world", "synth", "params"]).is_err());
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut parameters = Parameters {
+            synthesis: SynthesisParameters {
+                fs: 16000,
+                frame_period: 5.,
+                f0: vec![100.; 3],
+                spectrogram: SpectrogramLike::from_fn(3, 3, |_, _| 1.),
+                aperiodicity: SpectrogramLike::from_fn(3, 3, |_, _| 0.5),
+            },
+            samples: 160,
+        };
+        let args = TransformArgs {
+            pitch: 12.,
+            formant: 1.,
+            speed: 2.,
+        };
+        transform(&mut parameters, &args).unwrap();
+        assert_eq!(parameters.synthesis.f0, [200., 200.]);
+        assert_eq!(parameters.samples, 80);
+        let args = TransformArgs { speed: 0., ..args };
+        assert!(transform(&mut parameters, &args).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use world::error::WorldError;
+use world::signal_analyzer::AnalyzeResult;
+use world::spectrogram_like::SpectrogramLike;
+use world::synthesis::SynthesisParameters;
+
+const INFO_FILE_NAME: &str = "params.txt";
+const F0_FILE_NAME: &str = "f0.bin";
+const SPECTROGRAM_FILE_NAME: &str = "spectrogram.bin";
+const APERIODICITY_FILE_NAME: &str = "aperiodicity.bin";
+
+/// Parameters written to a directory by `analyze` and read by `synth`.
+///
+/// The directory has `params.txt` of `key = value` lines (fs, frame_period, samples, frames and bins),
+/// and `f0.bin`, `spectrogram.bin` and `aperiodicity.bin` of little endian f64 in frame-major order.
+pub struct Parameters {
+    pub synthesis: SynthesisParameters,
+    /// Length of the signal to synthesize, which is that of the analyzed signal.
+    pub samples: usize,
+}
+
+impl Parameters {
+    /// Returns an error unless f0, spectrogram and aperiodicity are all calculated.
+    pub fn from_result(result: &AnalyzeResult) -> Result<Parameters, WorldError> {
+        Ok(Parameters {
+            synthesis: SynthesisParameters::from_result(result)?,
+            samples: result.signal.len(),
+        })
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(dir)?;
+        let SynthesisParameters {
+            fs,
+            frame_period,
+            f0,
+            spectrogram,
+            aperiodicity,
+        } = &self.synthesis;
+        let info = format!(
+            "fs = {fs}\nframe_period = {frame_period}\nsamples = {}\nframes = {}\nbins = {}\n",
+            self.samples,
+            f0.len(),
+            spectrogram.frequency_axis_size()
+        );
+        fs::write(dir.join(INFO_FILE_NAME), info)?;
+        write_f64(&dir.join(F0_FILE_NAME), f0.iter())?;
+        write_f64(&dir.join(SPECTROGRAM_FILE_NAME), spectrogram.lines().flatten())?;
+        write_f64(&dir.join(APERIODICITY_FILE_NAME), aperiodicity.lines().flatten())?;
+        Ok(())
+    }
+
+    pub fn read(dir: &Path) -> Result<Parameters, Box<dyn Error>> {
+        let info = fs::read_to_string(dir.join(INFO_FILE_NAME))?;
+        let value = |key: &str| {
+            info.lines()
+                .filter_map(|line| line.split_once('='))
+                .find(|(k, _)| k.trim() == key)
+                .map(|(_, value)| value.trim().to_owned())
+                .ok_or_else(|| format!("{key} is missing in {INFO_FILE_NAME}"))
+        };
+        let fs = value("fs")?.parse()?;
+        let frame_period = value("frame_period")?.parse()?;
+        let samples = value("samples")?.parse()?;
+        let frames = value("frames")?.parse()?;
+        let bins = value("bins")?.parse()?;
+        let f0 = read_f64(&dir.join(F0_FILE_NAME), frames)?;
+        let spectrogram = read_spectrogram(&dir.join(SPECTROGRAM_FILE_NAME), frames, bins)?;
+        let aperiodicity = read_spectrogram(&dir.join(APERIODICITY_FILE_NAME), frames, bins)?;
+        Ok(Parameters {
+            synthesis: SynthesisParameters {
+                fs,
+                frame_period,
+                f0,
+                spectrogram,
+                aperiodicity,
+            },
+            samples,
+        })
+    }
+}
+
+fn write_f64<'a>(path: &Path, values: impl Iterator<Item = &'a f64>) -> Result<(), Box<dyn Error>> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for value in values {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn read_f64(path: &Path, len: usize) -> Result<Vec<f64>, Box<dyn Error>> {
+    let size = len
+        .checked_mul(size_of::<f64>())
+        .ok_or_else(|| format!("too many values {len} for {}", path.display()))?;
+    let mut bytes = Vec::new();
+    BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+    if bytes.len() != size {
+        return Err(format!("{} has {} bytes, but {} values are expected", path.display(), bytes.len(), len).into());
+    }
+    Ok(bytes
+        .chunks_exact(size_of::<f64>())
+        .map(|bytes| f64::from_le_bytes(bytes.try_into().unwrap()))
+        .collect())
+}
+
+fn read_spectrogram(path: &Path, frames: usize, bins: usize) -> Result<SpectrogramLike<f64>, Box<dyn Error>> {
+    let len = frames
+        .checked_mul(bins)
+        .ok_or_else(|| format!("too many values {frames}x{bins} for {}", path.display()))?;
+    let values = read_f64(path, len)?;
+    let mut spectrogram = SpectrogramLike::try_new(frames, bins)?;
+    for (line, values) in spectrogram.lines_mut().zip(values.chunks_exact(bins)) {
+        line.copy_from_slice(values);
+    }
+    Ok(spectrogram)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_read() {
+        let dir = std::env::temp_dir().join(format!("world-params-{}", std::process::id()));
+        let parameters = Parameters {
+            synthesis: SynthesisParameters {
+                fs: 16000,
+                frame_period: 5.,
+                f0: vec![0., 100., 110.],
+                spectrogram: SpectrogramLike::from_fn(3, 5, |frame, bin| (frame * 5 + bin) as f64),
+                aperiodicity: SpectrogramLike::from_fn(3, 5, |_, bin| bin as f64 / 4.),
+            },
+            samples: 321,
+        };
+        parameters.write(&dir).unwrap();
+        let Parameters { synthesis: read, samples } = Parameters::read(&dir).unwrap();
+        assert_eq!((read.fs, read.frame_period, samples), (16000, 5., 321));
+        assert_eq!(read.f0, parameters.synthesis.f0);
+        assert_eq!(
+            read.spectrogram.lines().collect::<Vec<_>>(),
+            parameters.synthesis.spectrogram.lines().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            read.aperiodicity.lines().collect::<Vec<_>>(),
+            parameters.synthesis.aperiodicity.lines().collect::<Vec<_>>()
+        );
+
+        fs::write(dir.join(F0_FILE_NAME), [0; 8]).unwrap();
+        assert!(Parameters::read(&dir).is_err());
+
+        let info = fs::read_to_string(dir.join(INFO_FILE_NAME)).unwrap();
+        fs::write(
+            dir.join(INFO_FILE_NAME),
+            info.replace("frames = 3", &format!("frames = {}", usize::MAX / 2)),
+        )
+        .unwrap();
+        assert!(Parameters::read(&dir).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
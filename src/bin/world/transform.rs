@@ -0,0 +1,131 @@
+use world::spectrogram_like::SpectrogramLike;
+
+use crate::params::Parameters;
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// Multiply f0 by `2^(semitones / 12)`, which keeps unvoiced frames unvoiced.
+pub fn shift_pitch(f0: &mut [f64], semitones: f64) {
+    let ratio = 2f64.powf(semitones / 12.);
+    f0.iter_mut().for_each(|f0| *f0 *= ratio);
+}
+
+/// Move the formants by `ratio` by stretching each spectral envelope along the frequency axis.
+///
+/// The bins above the stretched envelope take its last value.
+pub fn shift_formant(spectrogram: &mut SpectrogramLike<f64>, ratio: f64) {
+    let mut source = Vec::with_capacity(spectrogram.frequency_axis_size());
+    for line in spectrogram.lines_mut() {
+        source.clear();
+        source.extend_from_slice(line);
+        let last = source.len() - 1;
+        for (bin, value) in line.iter_mut().enumerate() {
+            let position = bin as f64 / ratio;
+            let index = position as usize;
+            *value = if index >= last {
+                source[last]
+            } else {
+                lerp(source[index], source[index + 1], position.fract())
+            };
+        }
+    }
+}
+
+/// Change the duration to `1 / speed` times by resampling the frames, and the length of the output with it.
+///
+/// At least 2 frames are kept, which the synthesis requires.
+/// f0 is interpolated only between voiced frames, and takes the nearest frame otherwise.
+/// The spectral envelope is interpolated in the log domain, and the aperiodicity linearly.
+pub fn change_speed(parameters: &mut Parameters, speed: f64) {
+    parameters.samples = (parameters.samples as f64 / speed).round() as usize;
+    let parameters = &mut parameters.synthesis;
+    let frames = parameters.f0.len();
+    let bins = parameters.spectrogram.frequency_axis_size();
+    let new_frames = (((frames - 1) as f64 / speed).round() as usize + 1).max(2);
+    let positions = (0..new_frames)
+        .map(|frame| {
+            let position = (frame as f64 * speed).min((frames - 1) as f64);
+            let index = position as usize;
+            (index, (index + 1).min(frames - 1), position - index as f64)
+        })
+        .collect::<Vec<_>>();
+
+    let f0 = &parameters.f0;
+    parameters.f0 = positions
+        .iter()
+        .map(|&(index, next, t)| match (f0[index], f0[next]) {
+            (a, b) if a > 0. && b > 0. => lerp(a, b, t),
+            (a, b) => {
+                if t < 0.5 {
+                    a
+                } else {
+                    b
+                }
+            }
+        })
+        .collect();
+    let resample = |source: &SpectrogramLike<f64>, interpolate: fn(f64, f64, f64) -> f64| {
+        let mut resampled = SpectrogramLike::new(new_frames, bins);
+        for (line, &(index, next, t)) in resampled.lines_mut().zip(&positions) {
+            let (a, b) = (source.line(index).unwrap(), source.line(next).unwrap());
+            for ((value, a), b) in line.iter_mut().zip(a).zip(b) {
+                *value = interpolate(*a, *b, t);
+            }
+        }
+        resampled
+    };
+    parameters.spectrogram = resample(&parameters.spectrogram, |a, b, t| a.powf(1. - t) * b.powf(t));
+    parameters.aperiodicity = resample(&parameters.aperiodicity, lerp);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use world::synthesis::SynthesisParameters;
+
+    #[test]
+    fn test_shift() {
+        let mut f0 = [0., 100., 220.];
+        shift_pitch(&mut f0, 12.);
+        assert_eq!(f0, [0., 200., 440.]);
+
+        let mut sp = SpectrogramLike::from_lines(&[&[1., 3., 5., 7., 9.]]);
+        shift_formant(&mut sp, 2.);
+        assert_eq!(sp.line(0).unwrap(), [1., 2., 3., 4., 5.]);
+        shift_formant(&mut sp, 0.5);
+        assert_eq!(sp.line(0).unwrap(), [1., 3., 5., 5., 5.]);
+    }
+
+    #[test]
+    fn test_change_speed() {
+        let mut parameters = Parameters {
+            synthesis: SynthesisParameters {
+                fs: 16000,
+                frame_period: 5.,
+                f0: vec![100., 200., 0.],
+                spectrogram: SpectrogramLike::from_lines(&[&[1., 1.], &[4., 1.], &[16., 1.]]),
+                aperiodicity: SpectrogramLike::from_lines(&[&[0., 1.], &[1., 1.], &[0., 0.]]),
+            },
+            samples: 200,
+        };
+        change_speed(&mut parameters, 0.5);
+        assert_eq!(parameters.synthesis.f0, [100., 150., 200., 0., 0.]);
+        assert_eq!(parameters.samples, 400);
+        assert_eq!(parameters.synthesis.spectrogram.time_axis_size(), 5);
+        assert_eq!(parameters.synthesis.spectrogram.line(1).unwrap(), [2., 1.]);
+        assert_eq!(parameters.synthesis.aperiodicity.line(3).unwrap(), [0.5, 0.5]);
+
+        change_speed(&mut parameters, 4.);
+        assert_eq!(parameters.synthesis.f0, [100., 0.]);
+        assert_eq!(parameters.samples, 100);
+        assert_eq!(parameters.synthesis.spectrogram.line(1).unwrap(), [16., 1.]);
+
+        // 3 frames at 5 times the speed would be 1 frame
+        change_speed(&mut parameters, 0.5);
+        change_speed(&mut parameters, 5.);
+        assert_eq!(parameters.synthesis.f0.len(), 2);
+        assert_eq!(parameters.synthesis.spectrogram.time_axis_size(), 2);
+    }
+}
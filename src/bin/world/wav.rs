@@ -0,0 +1,83 @@
+use std::error::Error;
+use std::path::Path;
+
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+
+/// Read a WAV file as mono samples in [-1, 1), averaging the channels.
+pub fn read(path: &Path) -> Result<(Vec<f64>, u32), Box<dyn Error>> {
+    let mut reader = WavReader::open(path)?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        SampleFormat::Float => reader
+            .samples::<f32>()
+            .map(|sample| sample.map(f64::from))
+            .collect::<Result<Vec<_>, _>>()?,
+        SampleFormat::Int => {
+            let scale = (1u64 << (spec.bits_per_sample - 1)) as f64;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f64 / scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+    let channels = spec.channels as usize;
+    let signal = samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f64>() / channels as f64)
+        .collect();
+    Ok((signal, spec.sample_rate))
+}
+
+/// Write mono samples as 16 bit PCM, or as 32 bit float if `float` is true.
+pub fn write(path: &Path, signal: &[f64], fs: u32, float: bool) -> Result<(), Box<dyn Error>> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: fs,
+        bits_per_sample: if float { 32 } else { 16 },
+        sample_format: if float { SampleFormat::Float } else { SampleFormat::Int },
+    };
+    let mut writer = WavWriter::create(path, spec)?;
+    for &sample in signal {
+        if float {
+            writer.write_sample(sample as f32)?;
+        } else {
+            writer.write_sample((sample * 32768.).round().clamp(-32768., 32767.) as i16)?;
+        }
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_read() {
+        let path = std::env::temp_dir().join(format!("world-wav-{}.wav", std::process::id()));
+        let signal = [0., 0.5, -0.5, -1., 2.];
+        write(&path, &signal, 16000, false).unwrap();
+        let (read_signal, fs) = read(&path).unwrap();
+        assert_eq!(fs, 16000);
+        // 16 bit integers clip at the full scale
+        assert_eq!(read_signal, [0., 0.5, -0.5, -1., 32767. / 32768.]);
+
+        write(&path, &signal, 22050, true).unwrap();
+        assert_eq!(read(&path).unwrap(), (signal.to_vec(), 22050));
+
+        // channels are averaged
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for sample in [16384i16, 0, -16384, -16384] {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        assert_eq!(read(&path).unwrap(), (vec![0.25, -0.5], 8000));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
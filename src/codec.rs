@@ -0,0 +1,145 @@
+//! Safe wrappers of codec.cpp, which codes the aperiodicity into bands and the spectral envelope into fewer dimensions.
+//!
+//! original: <https://github.com/mmorise/World/tree/v1.0.0/src/codec.cpp>
+
+use std::os::raw::c_int;
+
+use world_sys::{CodeAperiodicity, CodeSpectralEnvelope, DecodeAperiodicity, DecodeSpectralEnvelope, GetNumberOfAperiodicities};
+
+use crate::error::{validate_fs, WorldError};
+use crate::spectrogram_like::SpectrogramLike;
+
+/// FFT size of a spectrogram of `frequency_axis_size` bins, which must be a power of two.
+pub(crate) fn fft_size_of(frequency_axis_size: usize) -> Result<c_int, WorldError> {
+    let fft_size = (frequency_axis_size - 1) * 2;
+    match c_int::try_from(fft_size) {
+        Ok(fft_size_int) if fft_size.is_power_of_two() => Ok(fft_size_int),
+        _ => Err(WorldError::InvalidFFTSize(fft_size.try_into().unwrap_or(i32::MAX))),
+    }
+}
+
+fn time_axis_size(spectrogram: &SpectrogramLike<f64>) -> Result<c_int, WorldError> {
+    c_int::try_from(spectrogram.time_axis_size()).map_err(|_| WorldError::TooLongSignal(spectrogram.time_axis_size()))
+}
+
+/// Number of the bands of the coded aperiodicity, one per 3 kHz up to 15 kHz below the Nyquist frequency.
+pub fn number_of_aperiodicities(fs: u32) -> Result<usize, WorldError> {
+    let number = unsafe { GetNumberOfAperiodicities(validate_fs(fs)?) };
+    usize::try_from(number)
+        .ok()
+        .filter(|&number| number > 0)
+        .ok_or(WorldError::InvalidSampleRate(fs))
+}
+
+/// Code the aperiodicity into [`number_of_aperiodicities`] bands in decibels.
+pub fn code_aperiodicity(aperiodicity: &SpectrogramLike<f64>, fs: u32) -> Result<SpectrogramLike<f64>, WorldError> {
+    let number_of_aperiodicities = number_of_aperiodicities(fs)?;
+    let fft_size = fft_size_of(aperiodicity.frequency_axis_size())?;
+    let f0_length = time_axis_size(aperiodicity)?;
+    let mut coded_aperiodicity = SpectrogramLike::new(aperiodicity.time_axis_size(), number_of_aperiodicities);
+    unsafe {
+        CodeAperiodicity(aperiodicity.as_ptr(), f0_length, fs as c_int, fft_size, coded_aperiodicity.as_mut_ptr());
+    }
+    Ok(coded_aperiodicity)
+}
+
+/// Decode the aperiodicity coded by [`code_aperiodicity`] into `fft_size / 2 + 1` bins.
+///
+/// Frames whose bands are all close to 0 dB are regarded as unvoiced and decoded into almost 1.
+pub fn decode_aperiodicity(coded_aperiodicity: &SpectrogramLike<f64>, fs: u32, fft_size: i32) -> Result<SpectrogramLike<f64>, WorldError> {
+    let number_of_aperiodicities = number_of_aperiodicities(fs)?;
+    if coded_aperiodicity.frequency_axis_size() != number_of_aperiodicities {
+        return Err(WorldError::DifferentLength {
+            expected: number_of_aperiodicities,
+            actual: coded_aperiodicity.frequency_axis_size(),
+        });
+    }
+    if fft_size <= 0 || !(fft_size as u32).is_power_of_two() {
+        return Err(WorldError::InvalidFFTSize(fft_size));
+    }
+    let f0_length = time_axis_size(coded_aperiodicity)?;
+    let mut aperiodicity = SpectrogramLike::new(coded_aperiodicity.time_axis_size(), fft_size as usize / 2 + 1);
+    unsafe {
+        DecodeAperiodicity(coded_aperiodicity.as_ptr(), f0_length, fs as c_int, fft_size, aperiodicity.as_mut_ptr());
+    }
+    Ok(aperiodicity)
+}
+
+/// Code the spectral envelope into `number_of_dimensions` mel-cepstrum-like coefficients.
+///
+/// This is WORLD's own coding, which differs from the mel-cepstrum of SPTK.
+pub fn code_spectral_envelope(spectrogram: &SpectrogramLike<f64>, fs: u32, number_of_dimensions: usize) -> Result<SpectrogramLike<f64>, WorldError> {
+    let fs_int = validate_fs(fs)?;
+    let fft_size = fft_size_of(spectrogram.frequency_axis_size())?;
+    let f0_length = time_axis_size(spectrogram)?;
+    let dimensions = c_int::try_from(number_of_dimensions).map_err(|_| WorldError::InvalidParameter("number_of_dimensions"))?;
+    let mut coded_spectral_envelope = SpectrogramLike::try_new(spectrogram.time_axis_size(), number_of_dimensions)?;
+    unsafe {
+        CodeSpectralEnvelope(
+            spectrogram.as_ptr(),
+            f0_length,
+            fs_int,
+            fft_size,
+            dimensions,
+            coded_spectral_envelope.as_mut_ptr(),
+        );
+    }
+    Ok(coded_spectral_envelope)
+}
+
+/// Decode the spectral envelope coded by [`code_spectral_envelope`] into `fft_size / 2 + 1` bins.
+pub fn decode_spectral_envelope(coded_spectral_envelope: &SpectrogramLike<f64>, fs: u32, fft_size: i32) -> Result<SpectrogramLike<f64>, WorldError> {
+    let fs_int = validate_fs(fs)?;
+    if fft_size <= 0 || !(fft_size as u32).is_power_of_two() {
+        return Err(WorldError::InvalidFFTSize(fft_size));
+    }
+    let f0_length = time_axis_size(coded_spectral_envelope)?;
+    let dimensions = coded_spectral_envelope.frequency_axis_size() as c_int;
+    let mut spectrogram = SpectrogramLike::new(coded_spectral_envelope.time_axis_size(), fft_size as usize / 2 + 1);
+    unsafe {
+        DecodeSpectralEnvelope(
+            coded_spectral_envelope.as_ptr(),
+            f0_length,
+            fs_int,
+            fft_size,
+            dimensions,
+            spectrogram.as_mut_ptr(),
+        );
+    }
+    Ok(spectrogram)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aperiodicity() {
+        assert_eq!(number_of_aperiodicities(16000), Ok(1));
+        assert_eq!(number_of_aperiodicities(48000), Ok(5));
+        assert_eq!(number_of_aperiodicities(8000), Err(WorldError::InvalidSampleRate(8000)));
+
+        let mut aperiodicity = SpectrogramLike::new(2, 513);
+        for (i, value) in aperiodicity.line_mut(0).unwrap().iter_mut().enumerate() {
+            *value = 0.001 + 0.9 * i as f64 / 512.;
+        }
+        aperiodicity.line_mut(1).unwrap().fill(1.);
+        let coded = code_aperiodicity(&aperiodicity, 48000).unwrap();
+        assert_eq!((coded.time_axis_size(), coded.frequency_axis_size()), (2, 5));
+        assert!(coded.line(0).unwrap().windows(2).all(|bands| bands[0] < bands[1]));
+        assert!(coded.line(1).unwrap().iter().all(|&band| band.abs() < 1e-9));
+
+        let decoded = decode_aperiodicity(&coded, 48000, 1024).unwrap();
+        assert_eq!((decoded.time_axis_size(), decoded.frequency_axis_size()), (2, 513));
+        // the band centers are restored
+        for band in 0..5 {
+            let bin = 3000 * (band + 1) * 1024 / 48000;
+            assert!((decoded.line(0).unwrap()[bin] / aperiodicity.line(0).unwrap()[bin] - 1.).abs() < 1e-6);
+        }
+        assert!(decoded.line(1).unwrap().iter().all(|&value| value > 0.999));
+        assert_eq!(
+            decode_aperiodicity(&coded, 16000, 1024).err(),
+            Some(WorldError::DifferentLength { expected: 1, actual: 5 })
+        );
+    }
+}
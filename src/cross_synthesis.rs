@@ -0,0 +1,193 @@
+//! Cross-synthesis from f0, the spectral envelope and the aperiodicity of different analyses,
+//! e.g. singing with the timbre of another speaker, or a talkbox with f0 of an instrument.
+//!
+//! The parameters are resampled to a common frame grid starting at the first frame of each analysis,
+//! and the frames after the end of the envelope or the aperiodicity hold their last frame.
+//! Align the analyses by [`crate::dtw`] beforehand if they do not match in time.
+
+use crate::codec::fft_size_of;
+use crate::error::{validate_fs, WorldError};
+use crate::options::{f0_floor_for_cheaptrick, fft_size_for_cheaptrick};
+use crate::resample::{resample_aperiodicity, resample_spectrogram};
+use crate::signal_analyzer::AnalyzeResult;
+use crate::spectrogram_like::SpectrogramLike;
+use crate::synthesis::{SynthesisError, SynthesisParameters};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CrossSynthesisOption {
+    /// Sample rate of the output, fs of the envelope source by default.
+    pub fs: Option<u32>,
+    /// Frame period of the output in ms, that of the f0 source by default.
+    pub frame_period: Option<f64>,
+    /// FFT size of the output, by default the size which keeps the f0 floor of the envelope source at the output fs.
+    pub fft_size: Option<i32>,
+}
+
+impl CrossSynthesisOption {
+    pub fn new() -> CrossSynthesisOption {
+        CrossSynthesisOption::default()
+    }
+}
+
+/// Index of the frame before the position of `frame` on the grid of `frame_period`
+/// in the frames of `source_frame_period`, the next frame and the fraction between them.
+fn frame_position(frame: usize, frame_period: f64, source_frame_period: f64, source_frames: usize) -> (usize, usize, f64) {
+    let last = source_frames - 1;
+    let position = (frame as f64 * frame_period / source_frame_period).min(last as f64);
+    let index = position as usize;
+    (index, (index + 1).min(last), position - index as f64)
+}
+
+/// Interpolate voiced frames in the log domain, and take the nearer frame around unvoiced frames.
+/// Frames on the source grid are kept exactly.
+fn resample_f0(f0: &[f64], source_frame_period: f64, frame_period: f64) -> Vec<f64> {
+    let frames = ((f0.len() - 1) as f64 * source_frame_period / frame_period).round() as usize + 1;
+    (0..frames)
+        .map(|frame| match frame_position(frame, frame_period, source_frame_period, f0.len()) {
+            (index, _, 0.) => f0[index],
+            (index, next, t) if f0[index] > 0. && f0[next] > 0. => (f0[index].ln() * (1. - t) + f0[next].ln() * t).exp(),
+            (index, _, t) if t < 0.5 => f0[index],
+            (_, next, _) => f0[next],
+        })
+        .collect()
+}
+
+fn resample_frames(
+    source: &SpectrogramLike<f64>,
+    source_frame_period: f64,
+    frames: usize,
+    frame_period: f64,
+    interpolate: fn(f64, f64, f64) -> f64,
+) -> Result<SpectrogramLike<f64>, WorldError> {
+    let mut resampled = SpectrogramLike::try_new(frames, source.frequency_axis_size())?;
+    for (frame, line) in resampled.lines_mut().enumerate() {
+        let (index, next, t) = frame_position(frame, frame_period, source_frame_period, source.time_axis_size());
+        let (a, b) = (source.line(index).unwrap(), source.line(next).unwrap());
+        for (value, (a, b)) in line.iter_mut().zip(a.iter().zip(b)) {
+            *value = interpolate(*a, *b, t);
+        }
+    }
+    Ok(resampled)
+}
+
+fn validate_frame_period(frame_period: f64) -> Result<f64, WorldError> {
+    if frame_period.is_finite() && frame_period > 0. {
+        Ok(frame_period)
+    } else {
+        Err(WorldError::InvalidFramePeriod(frame_period))
+    }
+}
+
+/// Parameters for [`crate::synthesis::synthesis`] with f0 of `f0`, the spectral envelope of `spectrogram`
+/// and the aperiodicity of `aperiodicity`, which may be the same analysis.
+///
+/// The output has the duration of the f0 source. Returns [`SynthesisError::MissingParameter`]
+/// unless each analysis has the parameter taken from it.
+pub fn cross_synthesis(
+    f0: &AnalyzeResult,
+    spectrogram: &AnalyzeResult,
+    aperiodicity: &AnalyzeResult,
+    option: &CrossSynthesisOption,
+) -> Result<SynthesisParameters, WorldError> {
+    let (Some(f0_source), Some(spectrogram_source), Some(aperiodicity_source)) = (&f0.f0, &spectrogram.spectrogram, &aperiodicity.aperiodicity)
+    else {
+        return Err(SynthesisError::MissingParameter.into());
+    };
+    if f0_source.is_empty() || spectrogram_source.time_axis_size() == 0 || aperiodicity_source.time_axis_size() == 0 {
+        return Err(WorldError::EmptySignal);
+    }
+    let spectrogram_fs = validate_fs(spectrogram.fs as u32)? as u32;
+    let aperiodicity_fs = validate_fs(aperiodicity.fs as u32)? as u32;
+    let fs = option.fs.unwrap_or(spectrogram_fs);
+    validate_fs(fs)?;
+    let frame_period = validate_frame_period(option.frame_period.unwrap_or(f0.frame_period))?;
+    let fft_size = match option.fft_size {
+        Some(fft_size) => {
+            f0_floor_for_cheaptrick(fs, fft_size)?;
+            fft_size
+        }
+        None => {
+            let f0_floor = f0_floor_for_cheaptrick(spectrogram_fs, fft_size_of(spectrogram_source.frequency_axis_size())?)?;
+            fft_size_for_cheaptrick(fs, f0_floor)?
+        }
+    };
+
+    let f0_values = resample_f0(f0_source, validate_frame_period(f0.frame_period)?, frame_period);
+    let frames = f0_values.len();
+    let spectrogram_frames = resample_frames(
+        spectrogram_source,
+        validate_frame_period(spectrogram.frame_period)?,
+        frames,
+        frame_period,
+        |a, b, t| a.powf(1. - t) * b.powf(t),
+    )?;
+    let aperiodicity_frames = resample_frames(
+        aperiodicity_source,
+        validate_frame_period(aperiodicity.frame_period)?,
+        frames,
+        frame_period,
+        |a, b, t| a * (1. - t) + b * t,
+    )?;
+
+    Ok(SynthesisParameters {
+        fs,
+        frame_period,
+        f0: f0_values,
+        spectrogram: resample_spectrogram(&spectrogram_frames, spectrogram_fs, fs, fft_size)?,
+        aperiodicity: resample_aperiodicity(&aperiodicity_frames, aperiodicity_fs, fs, fft_size)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(fs: i32, frame_period: f64, f0: Option<&[f64]>, bins: usize, value: impl Fn(usize) -> f64) -> AnalyzeResult {
+        let spectrogram = SpectrogramLike::from_fn(f0.map_or(3, <[f64]>::len), bins, |frame, _| value(frame));
+        AnalyzeResult {
+            signal: Box::new([]),
+            range: 0..0,
+            fs,
+            frame_period,
+            f0: f0.map(Box::from),
+            temporal_positions: None,
+            aperiodicity: Some(spectrogram.clone()),
+            spectrogram: Some(spectrogram),
+        }
+    }
+
+    #[test]
+    fn test_cross_synthesis() {
+        let melody = result(16000, 5., Some(&[0., 100., 200., 200., 400.]), 513, |_| 0.5);
+        // 10 ms frames at 48 kHz with fft_size 2048
+        let timbre = result(48000, 10., None, 1025, |frame| 10f64.powi(frame as i32));
+        let parameters = cross_synthesis(&melody, &timbre, &melody, &CrossSynthesisOption::new()).unwrap();
+        assert_eq!((parameters.fs, parameters.frame_period), (48000, 5.));
+        assert_eq!(parameters.f0, [0., 100., 200., 200., 400.]);
+        assert_eq!(parameters.spectrogram.time_axis_size(), 5);
+        assert_eq!(parameters.spectrogram.frequency_axis_size(), 1025);
+        assert_eq!(parameters.aperiodicity.frequency_axis_size(), 1025);
+        // every other frame lies between two frames of the envelope, and the frame after the end holds the last frame
+        let levels = parameters.spectrogram.lines().map(|line| line[0].log10()).collect::<Vec<_>>();
+        for (level, expected) in levels.iter().zip([0., 0.5, 1., 1.5, 2.]) {
+            assert!((level - expected).abs() < 1e-9, "{levels:?}");
+        }
+        assert!(parameters.aperiodicity.lines().flatten().all(|&ap| (ap - 0.5).abs() < 1e-12));
+
+        let option = CrossSynthesisOption {
+            frame_period: Some(2.5),
+            fs: Some(16000),
+            ..CrossSynthesisOption::new()
+        };
+        let parameters = cross_synthesis(&melody, &timbre, &melody, &option).unwrap();
+        assert_eq!(parameters.f0.len(), 9);
+        assert!((parameters.f0[3] - 200f64.sqrt() * 10.).abs() < 1e-9);
+        assert_eq!(parameters.f0[1], 100.);
+        assert_eq!(parameters.spectrogram.frequency_axis_size(), 513);
+
+        assert_eq!(
+            cross_synthesis(&timbre, &timbre, &melody, &CrossSynthesisOption::new()).err(),
+            Some(WorldError::Synthesis(SynthesisError::MissingParameter))
+        );
+    }
+}
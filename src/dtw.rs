@@ -0,0 +1,251 @@
+//! Dynamic time warping between two sequences of frames, e.g. for voice conversion and prosody transfer.
+//!
+//! The path moves by one frame of either or both sequences at each step, and a Sakoe-Chiba band
+//! around the diagonal from the first frames to the last frames restricts the path if given.
+
+use std::ops::Range;
+
+use crate::error::WorldError;
+use crate::mel_cepstrum::{alpha_for_fs, spectrogram_to_mel_cepstrum};
+use crate::metrics::mel_cepstral_distance;
+use crate::spectrogram_like::SpectrogramLike;
+use crate::synthesis::SynthesisParameters;
+
+/// Path of the alignment from the frames of the source to the frames of the reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarpingPath {
+    /// Pairs of the reference frame and the source frame from `(0, 0)` to the last frames,
+    /// where both increase by at most 1 at each step.
+    pub pairs: Vec<(usize, usize)>,
+    /// Total distance along the path.
+    pub cost: f64,
+}
+
+/// Accumulated costs of the cells in the band, row by row of the reference frames, which are infinite outside the band.
+struct AccumulatedCosts {
+    /// Source frames in the band of each reference frame.
+    columns: Vec<Range<usize>>,
+    /// Index in `costs` of the first cell of each row.
+    offsets: Vec<usize>,
+    costs: Vec<f64>,
+}
+
+impl AccumulatedCosts {
+    fn new(columns: Vec<Range<usize>>) -> AccumulatedCosts {
+        let mut offsets = Vec::with_capacity(columns.len());
+        let mut len = 0;
+        for columns in &columns {
+            offsets.push(len);
+            len += columns.len();
+        }
+        AccumulatedCosts {
+            columns,
+            offsets,
+            costs: vec![f64::INFINITY; len],
+        }
+    }
+
+    fn get(&self, i: usize, j: usize) -> f64 {
+        let columns = &self.columns[i];
+        if columns.contains(&j) {
+            self.costs[self.offsets[i] + j - columns.start]
+        } else {
+            f64::INFINITY
+        }
+    }
+}
+
+/// Align the frames of `source` to the frames of `reference` by `distance` between two frames.
+///
+/// `band` is the radius of the Sakoe-Chiba band in frames, and the alignment fails with [`WorldError::InvalidParameter`]
+/// if the band is too narrow to connect the first and the last frames.
+/// Only the costs in the band are kept, so without `band` the memory grows with the product of the numbers of the frames,
+/// e.g. about 1 GB for two sequences of 12000 frames.
+pub fn dtw(
+    reference: &SpectrogramLike<f64>,
+    source: &SpectrogramLike<f64>,
+    band: Option<usize>,
+    distance: impl Fn(&[f64], &[f64]) -> f64,
+) -> Result<WarpingPath, WorldError> {
+    let (n, m) = (reference.time_axis_size(), source.time_axis_size());
+    let columns = (0..n)
+        .map(|i| match band {
+            Some(radius) => {
+                let center = if n > 1 { i as f64 * (m - 1) as f64 / (n - 1) as f64 } else { 0. };
+                let start = (center - radius as f64).ceil().max(0.) as usize;
+                let end = ((center + radius as f64).floor() as usize + 1).min(m);
+                start..end.max(start)
+            }
+            None => 0..m,
+        })
+        .collect();
+
+    let mut accumulated = AccumulatedCosts::new(columns);
+    for (i, reference) in reference.lines().enumerate() {
+        for j in accumulated.columns[i].clone() {
+            let previous = match (i, j) {
+                (0, 0) => 0.,
+                (0, j) => accumulated.get(0, j - 1),
+                (i, 0) => accumulated.get(i - 1, 0),
+                (i, j) => accumulated
+                    .get(i - 1, j - 1)
+                    .min(accumulated.get(i - 1, j))
+                    .min(accumulated.get(i, j - 1)),
+            };
+            let index = accumulated.offsets[i] + j - accumulated.columns[i].start;
+            accumulated.costs[index] = previous + distance(reference, source.line(j).unwrap());
+        }
+    }
+    let cost = accumulated.get(n - 1, m - 1);
+    if !cost.is_finite() {
+        return Err(WorldError::InvalidParameter("band"));
+    }
+
+    let mut pairs = vec![(n - 1, m - 1)];
+    let (mut i, mut j) = (n - 1, m - 1);
+    while (i, j) != (0, 0) {
+        // the diagonal is preferred on ties
+        (i, j) = [(i.wrapping_sub(1), j.wrapping_sub(1)), (i.wrapping_sub(1), j), (i, j.wrapping_sub(1))]
+            .into_iter()
+            .filter(|&(i, j)| i < n && j < m)
+            .min_by(|&(i1, j1), &(i2, j2)| accumulated.get(i1, j1).total_cmp(&accumulated.get(i2, j2)))
+            .unwrap();
+        pairs.push((i, j));
+    }
+    pairs.reverse();
+    Ok(WarpingPath { pairs, cost })
+}
+
+/// Align two spectral envelopes of the same size by [`mel_cepstral_distance`] of the mel-cepstrum of `order`
+/// with the alpha of [`alpha_for_fs`].
+pub fn dtw_spectrograms(
+    reference: &SpectrogramLike<f64>,
+    source: &SpectrogramLike<f64>,
+    fs: u32,
+    order: usize,
+    band: Option<usize>,
+) -> Result<WarpingPath, WorldError> {
+    let alpha = alpha_for_fs(fs);
+    let reference = spectrogram_to_mel_cepstrum(reference, order, alpha)?;
+    let source = spectrogram_to_mel_cepstrum(source, order, alpha)?;
+    dtw(&reference, &source, band, mel_cepstral_distance)
+}
+
+impl WarpingPath {
+    /// Number of the frames of the reference.
+    pub fn reference_len(&self) -> usize {
+        self.pairs.last().map_or(0, |&(i, _)| i + 1)
+    }
+
+    /// Number of the frames of the source.
+    pub fn source_len(&self) -> usize {
+        self.pairs.last().map_or(0, |&(_, j)| j + 1)
+    }
+
+    /// Source frame for each reference frame, which is the middle of the source frames aligned to it.
+    pub fn source_frames(&self) -> Vec<usize> {
+        let mut frames = Vec::with_capacity(self.reference_len());
+        for pairs in self.pairs.chunk_by(|a, b| a.0 == b.0) {
+            frames.push(pairs[(pairs.len() - 1) / 2].1);
+        }
+        frames
+    }
+
+    fn validate_source_len(&self, len: usize) -> Result<(), WorldError> {
+        if len == self.source_len() {
+            Ok(())
+        } else {
+            Err(WorldError::DifferentLength {
+                expected: self.source_len(),
+                actual: len,
+            })
+        }
+    }
+
+    /// Values of the source frames such as f0, warped into the frames of the reference.
+    pub fn warp<T: Copy>(&self, frames: &[T]) -> Result<Vec<T>, WorldError> {
+        self.validate_source_len(frames.len())?;
+        Ok(self.source_frames().into_iter().map(|frame| frames[frame]).collect())
+    }
+
+    /// Spectrogram-like of the source frames, warped into the frames of the reference.
+    pub fn warp_spectrogram(&self, spectrogram: &SpectrogramLike<f64>) -> Result<SpectrogramLike<f64>, WorldError> {
+        self.validate_source_len(spectrogram.time_axis_size())?;
+        let mut warped = SpectrogramLike::try_new(self.reference_len(), spectrogram.frequency_axis_size())?;
+        for (line, frame) in warped.lines_mut().zip(self.source_frames()) {
+            line.copy_from_slice(spectrogram.line(frame).unwrap());
+        }
+        Ok(warped)
+    }
+
+    /// f0, the spectral envelope and the aperiodicity of the source, warped into the frames of the reference,
+    /// so that they have as many frames as the reference.
+    pub fn apply(&self, parameters: &SynthesisParameters) -> Result<SynthesisParameters, WorldError> {
+        Ok(SynthesisParameters {
+            f0: self.warp(&parameters.f0)?,
+            spectrogram: self.warp_spectrogram(&parameters.spectrogram)?,
+            aperiodicity: self.warp_spectrogram(&parameters.aperiodicity)?,
+            ..*parameters
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(values: &[f64]) -> SpectrogramLike<f64> {
+        SpectrogramLike::from_fn(values.len(), 1, |frame, _| values[frame])
+    }
+
+    fn distance(a: &[f64], b: &[f64]) -> f64 {
+        (a[0] - b[0]).abs()
+    }
+
+    #[test]
+    fn test_dtw() {
+        let reference = frames(&[0., 1., 2., 3., 4.]);
+        let source = frames(&[0., 0., 1., 1., 1., 2., 4.]);
+        let path = dtw(&reference, &source, None, distance).unwrap();
+        assert_eq!(path.cost, 1.);
+        assert_eq!(path.pairs.first(), Some(&(0, 0)));
+        assert_eq!(path.pairs.last(), Some(&(4, 6)));
+        assert!(path.pairs.windows(2).all(|pair| {
+            let (di, dj) = (pair[1].0 - pair[0].0, pair[1].1 - pair[0].1);
+            di <= 1 && dj <= 1 && di + dj > 0
+        }));
+        assert_eq!(path.source_frames(), [0, 3, 5, 5, 6]);
+
+        let f0 = [100., 110., 120., 130., 140., 150., 160.];
+        assert_eq!(path.warp(&f0).unwrap(), [100., 130., 150., 150., 160.]);
+        let warped = path.warp_spectrogram(&source).unwrap();
+        assert_eq!(warped.lines().map(|line| line[0]).collect::<Vec<_>>(), [0., 1., 2., 2., 4.]);
+        assert_eq!(path.warp(&f0[1..]), Err(WorldError::DifferentLength { expected: 7, actual: 6 }));
+        let parameters = SynthesisParameters {
+            fs: 16000,
+            frame_period: 5.,
+            f0: f0.to_vec(),
+            spectrogram: source.clone(),
+            aperiodicity: source.clone(),
+        };
+        let applied = path.apply(&parameters).unwrap();
+        assert_eq!((applied.fs, applied.frame_period), (16000, 5.));
+        assert_eq!(applied.f0, [100., 130., 150., 150., 160.]);
+        assert_eq!(applied.aperiodicity.time_axis_size(), 5);
+
+        // the band keeps the path near the diagonal
+        let banded = dtw(&reference, &source, Some(1), distance).unwrap();
+        assert!(banded.cost >= path.cost);
+        assert!(banded.pairs.iter().all(|&(i, j)| (j as f64 - i as f64 * 6. / 4.).abs() <= 1.));
+        assert_eq!(
+            dtw(&reference, &frames(&[0.; 20]), Some(0), distance),
+            Err(WorldError::InvalidParameter("band"))
+        );
+
+        // the band keeps only about 5 costs per frame instead of 10^10 in total
+        let long = frames(&(0..100000).map(f64::from).collect::<Vec<_>>());
+        let path = dtw(&long, &long, Some(2), distance).unwrap();
+        assert_eq!(path.cost, 0.);
+        assert_eq!(path.pairs.len(), 100000);
+    }
+}
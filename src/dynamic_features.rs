@@ -0,0 +1,211 @@
+//! Dynamic (delta) features and Maximum Likelihood Parameter Generation (MLPG) for statistical parametric synthesis.
+//!
+//! A window is a slice of odd length, whose center is applied to the current frame, e.g. [`DELTA_WINDOW`].
+//! Features of `D` dimensions with `N` windows give frames of `D * (N + 1)` dimensions,
+//! which are the static features followed by the features of each window, same as the streams of HTS and Merlin.
+//!
+//! Same as Merlin, [`append_dynamic_features`] repeats the first and the last frames outside the sequence,
+//! while [`mlpg`] drops the coefficients of the windows outside the sequence.
+
+use crate::error::WorldError;
+use crate::spectrogram_like::SpectrogramLike;
+
+/// First order regression window of HTS.
+pub const DELTA_WINDOW: [f64; 3] = [-0.5, 0., 0.5];
+/// Second order regression window of HTS.
+pub const DELTA_DELTA_WINDOW: [f64; 3] = [1., -2., 1.];
+
+const STATIC_WINDOW: [f64; 1] = [1.];
+
+fn validate_windows(windows: &[&[f64]]) -> Result<(), WorldError> {
+    if windows
+        .iter()
+        .all(|window| !window.len().is_multiple_of(2) && window.iter().all(|c| c.is_finite()))
+    {
+        Ok(())
+    } else {
+        Err(WorldError::InvalidParameter("windows"))
+    }
+}
+
+/// The static window followed by `windows`.
+fn all_windows<'a>(windows: &[&'a [f64]]) -> Vec<&'a [f64]> {
+    std::iter::once(&STATIC_WINDOW[..]).chain(windows.iter().copied()).collect()
+}
+
+/// Frames which `window` at `frame` covers in `0..len`, with the coefficients.
+fn window_taps(window: &[f64], frame: usize, len: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+    let half = window.len() / 2;
+    window
+        .iter()
+        .enumerate()
+        .filter_map(move |(j, &coefficient)| (frame + j).checked_sub(half).filter(|&t| t < len).map(|t| (t, coefficient)))
+}
+
+/// Frames which `window` at `frame` covers, with the frames outside `0..len` replaced by the nearest end.
+fn padded_window_taps(window: &[f64], frame: usize, len: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+    let half = window.len() / 2;
+    window
+        .iter()
+        .enumerate()
+        .map(move |(j, &coefficient)| ((frame + j).saturating_sub(half).min(len - 1), coefficient))
+}
+
+/// Static features followed by the features of `windows`, e.g. `&[&DELTA_WINDOW, &DELTA_DELTA_WINDOW]`.
+pub fn append_dynamic_features(features: &SpectrogramLike<f64>, windows: &[&[f64]]) -> Result<SpectrogramLike<f64>, WorldError> {
+    validate_windows(windows)?;
+    let (len, dimension) = (features.time_axis_size(), features.frequency_axis_size());
+    let windows = all_windows(windows);
+    let mut out = SpectrogramLike::try_new(len, dimension * windows.len())?;
+    for (frame, out) in out.lines_mut().enumerate() {
+        for (window, out) in windows.iter().zip(out.chunks_exact_mut(dimension)) {
+            out.fill(0.);
+            for (t, coefficient) in padded_window_taps(window, frame, len) {
+                for (out, feature) in out.iter_mut().zip(features.line(t).unwrap()) {
+                    *out += coefficient * feature;
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Static features of the maximum likelihood under the means and the variances of the static and dynamic features,
+/// which have the layout of [`append_dynamic_features`] with the same `windows`.
+///
+/// The variances must be positive. The result is smooth, and gives e.g. log f0 or mel-cepstrum for the synthesis.
+pub fn mlpg(means: &SpectrogramLike<f64>, variances: &SpectrogramLike<f64>, windows: &[&[f64]]) -> Result<SpectrogramLike<f64>, WorldError> {
+    validate_windows(windows)?;
+    if means.time_axis_size() != variances.time_axis_size() || means.frequency_axis_size() != variances.frequency_axis_size() {
+        return Err(WorldError::DifferentLength {
+            expected: means.time_axis_size() * means.frequency_axis_size(),
+            actual: variances.time_axis_size() * variances.frequency_axis_size(),
+        });
+    }
+    let windows = all_windows(windows);
+    if !means.frequency_axis_size().is_multiple_of(windows.len()) {
+        return Err(WorldError::InvalidParameter("dimension of means"));
+    }
+    if !variances.lines().flatten().all(|&variance| variance > 0. && variance.is_finite()) {
+        return Err(WorldError::InvalidParameter("variances"));
+    }
+    let len = means.time_axis_size();
+    let dimension = means.frequency_axis_size() / windows.len();
+    let bandwidth = windows.iter().map(|window| window.len() - 1).max().unwrap_or(0);
+    let mut out = SpectrogramLike::try_new(len, dimension)?;
+
+    // upper band of W^T Σ^-1 W, where band[t][k] is the element at (t, t + k)
+    let mut band = vec![vec![0.; bandwidth + 1]; len];
+    let mut rhs = vec![0.; len];
+    for d in 0..dimension {
+        band.iter_mut().for_each(|row| row.fill(0.));
+        rhs.fill(0.);
+        for (w, window) in windows.iter().enumerate() {
+            for (frame, (mean, variance)) in means.lines().zip(variances.lines()).enumerate() {
+                let precision = 1. / variance[w * dimension + d];
+                let mean = mean[w * dimension + d];
+                for (t1, c1) in window_taps(window, frame, len) {
+                    rhs[t1] += precision * c1 * mean;
+                    for (t2, c2) in window_taps(window, frame, len).filter(|&(t2, _)| t2 >= t1) {
+                        band[t1][t2 - t1] += precision * c1 * c2;
+                    }
+                }
+            }
+        }
+        let solution = solve_banded(&mut band, &rhs)?;
+        for (out, value) in out.lines_mut().zip(solution) {
+            out[d] = value;
+        }
+    }
+    Ok(out)
+}
+
+/// Solve the symmetric positive definite banded system by the Cholesky decomposition, overwriting `band` with the factor.
+fn solve_banded(band: &mut [Vec<f64>], rhs: &[f64]) -> Result<Vec<f64>, WorldError> {
+    let len = rhs.len();
+    let bandwidth = band.first().map_or(0, |row| row.len() - 1);
+    // band[t][k] becomes the element at (t, t + k) of the upper triangular U with A = U^T U
+    for t in 0..len {
+        for k in 1..=bandwidth.min(t) {
+            let s = t - k;
+            for j in 0..=bandwidth - k {
+                let value = band[s][k] * band[s][k + j];
+                band[t][j] -= value;
+            }
+        }
+        let diagonal = band[t][0];
+        if diagonal <= 0. || !diagonal.is_finite() {
+            return Err(WorldError::InvalidParameter("variances"));
+        }
+        let diagonal = diagonal.sqrt();
+        band[t][0] = diagonal;
+        for value in &mut band[t][1..] {
+            *value /= diagonal;
+        }
+    }
+    // U^T y = rhs
+    let mut y = rhs.to_vec();
+    for t in 0..len {
+        for k in 1..=bandwidth.min(t) {
+            y[t] -= band[t - k][k] * y[t - k];
+        }
+        y[t] /= band[t][0];
+    }
+    // U x = y
+    for t in (0..len).rev() {
+        for k in 1..=bandwidth.min(len - 1 - t) {
+            y[t] -= band[t][k] * y[t + k];
+        }
+        y[t] /= band[t][0];
+    }
+    Ok(y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOWS: [&[f64]; 2] = [&DELTA_WINDOW, &DELTA_DELTA_WINDOW];
+
+    #[test]
+    fn test_append_dynamic_features() {
+        let static_features = SpectrogramLike::from_lines(&[[1., 0.], [2., 0.], [4., 1.], [7., 1.]]);
+        let all = append_dynamic_features(&static_features, &WINDOWS).unwrap();
+        assert_eq!((all.time_axis_size(), all.frequency_axis_size()), (4, 6));
+        assert_eq!(all.line(1).unwrap(), [2., 0., 1.5, 0.5, 1., 1.]);
+        // the first and the last frames are repeated outside the sequence
+        assert_eq!(all.line(0).unwrap(), [1., 0., 0.5, 0., 1., 0.]);
+        assert_eq!(all.line(3).unwrap(), [7., 1., 1.5, 0., -3., 0.]);
+        assert_eq!(
+            append_dynamic_features(&static_features, &[&[1., 1.]]).err(),
+            Some(WorldError::InvalidParameter("windows"))
+        );
+    }
+
+    #[test]
+    fn test_mlpg() {
+        // consistent means give the static features, where zero at both ends makes both paddings the same
+        let static_features = SpectrogramLike::from_lines(&[[0., 0.], [2., -1.], [4., 1.], [7., 1.], [0., 0.]]);
+        let means = append_dynamic_features(&static_features, &WINDOWS).unwrap();
+        let mut variances = SpectrogramLike::from_fn(5, 6, |_, _| 0.5);
+        let generated = mlpg(&means, &variances, &WINDOWS).unwrap();
+        for (expected, actual) in static_features.lines().flatten().zip(generated.lines().flatten()) {
+            assert!((expected - actual).abs() < 1e-9, "{expected} != {actual}");
+        }
+
+        // zero deltas with confident deltas smooth the steps of the static means
+        let mut means = SpectrogramLike::new(5, 6);
+        for (t, line) in means.lines_mut().enumerate() {
+            line[0] = if t < 2 { 0. } else { 1. };
+        }
+        for line in variances.lines_mut() {
+            line.copy_from_slice(&[1., 1., 0.01, 0.01, 0.01, 0.01]);
+        }
+        let generated = mlpg(&means, &variances, &WINDOWS).unwrap();
+        let trajectory = generated.lines().map(|line| line[0]).collect::<Vec<_>>();
+        assert!(trajectory.windows(2).all(|pair| (pair[1] - pair[0]).abs() < 0.5));
+
+        variances.line_mut(0).unwrap()[0] = 0.;
+        assert_eq!(mlpg(&means, &variances, &WINDOWS).err(), Some(WorldError::InvalidParameter("variances")));
+    }
+}
@@ -0,0 +1,79 @@
+use crate::synthesis::SynthesisError;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Error of invalid parameters, detected before they are passed to WORLD.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldError {
+    InvalidSampleRate(u32),
+    InvalidF0Floor(f64),
+    InvalidF0Range { f0_floor: f64, f0_ceil: f64 },
+    InvalidFramePeriod(f64),
+    InvalidFFTSize(i32),
+    InvalidParameter(&'static str),
+    DifferentLength { expected: usize, actual: usize },
+    EmptySignal,
+    TooLongSignal(usize),
+    NonFiniteSignal,
+    InvalidRange { start: usize, end: usize },
+    InvalidSpectrogramSize { time_axis_size: usize, frequency_axis_size: usize },
+    Synthesis(SynthesisError),
+}
+
+impl Display for WorldError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorldError::InvalidSampleRate(fs) => write!(f, "invalid sample rate {fs}"),
+            WorldError::InvalidF0Floor(f0_floor) => write!(f, "invalid f0_floor {f0_floor}"),
+            WorldError::InvalidF0Range { f0_floor, f0_ceil } => write!(f, "invalid f0 range {f0_floor}..{f0_ceil}"),
+            WorldError::InvalidFramePeriod(frame_period) => write!(f, "invalid frame period {frame_period}"),
+            WorldError::InvalidFFTSize(fft_size) => write!(f, "invalid fft size {fft_size}"),
+            WorldError::InvalidParameter(name) => write!(f, "invalid {name}"),
+            WorldError::DifferentLength { expected, actual } => write!(f, "expected length {expected} but got {actual}"),
+            WorldError::EmptySignal => write!(f, "empty signal"),
+            WorldError::TooLongSignal(len) => write!(f, "too long signal of {len} samples"),
+            WorldError::NonFiniteSignal => write!(f, "signal contains NaN or infinity"),
+            WorldError::InvalidRange { start, end } => write!(f, "invalid range {start}..{end}"),
+            WorldError::InvalidSpectrogramSize {
+                time_axis_size,
+                frequency_axis_size,
+            } => write!(f, "invalid spectrogram size {time_axis_size}x{frequency_axis_size}"),
+            WorldError::Synthesis(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for WorldError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WorldError::Synthesis(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<SynthesisError> for WorldError {
+    fn from(e: SynthesisError) -> Self {
+        WorldError::Synthesis(e)
+    }
+}
+
+pub(crate) fn validate_fs(fs: u32) -> Result<i32, WorldError> {
+    match i32::try_from(fs) {
+        Ok(fs) if fs > 0 => Ok(fs),
+        _ => Err(WorldError::InvalidSampleRate(fs)),
+    }
+}
+
+pub(crate) fn validate_signal(signal: &[f64]) -> Result<(), WorldError> {
+    if signal.is_empty() {
+        return Err(WorldError::EmptySignal);
+    }
+    if signal.len() > i32::MAX as usize {
+        return Err(WorldError::TooLongSignal(signal.len()));
+    }
+    if !signal.iter().all(|x| x.is_finite()) {
+        return Err(WorldError::NonFiniteSignal);
+    }
+    Ok(())
+}
@@ -0,0 +1,305 @@
+//! Safe wrappers of the real FFT and the minimum phase analysis in WORLD.
+//!
+//! Complex values are represented as `[real, imaginary]`, same as `fft_complex` in WORLD.
+//! Only the non-negative frequency bins (`fft_size / 2 + 1` values) of spectra are exposed.
+
+use std::mem::MaybeUninit;
+use std::{ptr, slice};
+
+use world_sys::{
+    fft_execute, DestroyForwardRealFFT, DestroyInverseRealFFT, DestroyMinimumPhaseAnalysis, GetMinimumPhaseSpectrum, InitializeForwardRealFFT,
+    InitializeInverseRealFFT, InitializeMinimumPhaseAnalysis,
+};
+
+use crate::error::WorldError;
+
+fn validate_fft_size(fft_size: usize) -> Result<i32, WorldError> {
+    match i32::try_from(fft_size) {
+        Ok(size) if size >= 4 && fft_size.is_power_of_two() => Ok(size),
+        _ => Err(WorldError::InvalidFFTSize(fft_size.try_into().unwrap_or(i32::MAX))),
+    }
+}
+
+fn validate_length(expected: usize, actual: usize) -> Result<(), WorldError> {
+    if expected != actual {
+        return Err(WorldError::DifferentLength { expected, actual });
+    }
+    Ok(())
+}
+
+/// FFT from a real waveform of `fft_size` samples to its spectrum of `fft_size / 2 + 1` bins.
+pub struct ForwardRealFFT {
+    inner: world_sys::ForwardRealFFT,
+}
+
+// the buffers and the plan are owned only by this value
+unsafe impl Send for ForwardRealFFT {}
+
+impl ForwardRealFFT {
+    /// # Panics
+    /// Panics if `fft_size` is not a power of two or is smaller than 4.
+    pub fn new(fft_size: usize) -> ForwardRealFFT {
+        ForwardRealFFT::try_new(fft_size).unwrap()
+    }
+
+    pub fn try_new(fft_size: usize) -> Result<ForwardRealFFT, WorldError> {
+        let fft_size = validate_fft_size(fft_size)?;
+        let inner = unsafe {
+            let mut inner = MaybeUninit::uninit();
+            InitializeForwardRealFFT(fft_size, inner.as_mut_ptr());
+            inner.assume_init()
+        };
+        Ok(ForwardRealFFT { inner })
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.inner.fft_size as usize
+    }
+
+    /// Input buffer of `fft_size` samples.
+    pub fn waveform(&self) -> &[f64] {
+        unsafe { slice::from_raw_parts(self.inner.waveform, self.fft_size()) }
+    }
+
+    pub fn waveform_mut(&mut self) -> &mut [f64] {
+        unsafe { slice::from_raw_parts_mut(self.inner.waveform, self.fft_size()) }
+    }
+
+    /// Output buffer of `fft_size / 2 + 1` bins, which is updated by [`ForwardRealFFT::execute`].
+    pub fn spectrum(&self) -> &[[f64; 2]] {
+        unsafe { slice::from_raw_parts(self.inner.spectrum, self.fft_size() / 2 + 1) }
+    }
+
+    /// Transform the waveform buffer into the spectrum buffer.
+    pub fn execute(&mut self) {
+        unsafe { fft_execute(ptr::read(&self.inner.forward_fft)) }
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const world_sys::ForwardRealFFT {
+        &self.inner
+    }
+
+    /// Transform `waveform` into `spectrum`.
+    ///
+    /// `waveform` is zero-padded to `fft_size` samples, and `spectrum` must have `fft_size / 2 + 1` bins.
+    pub fn process(&mut self, waveform: &[f64], spectrum: &mut [[f64; 2]]) -> Result<(), WorldError> {
+        if waveform.len() > self.fft_size() {
+            return Err(WorldError::TooLongSignal(waveform.len()));
+        }
+        validate_length(self.fft_size() / 2 + 1, spectrum.len())?;
+        let buffer = self.waveform_mut();
+        buffer[..waveform.len()].copy_from_slice(waveform);
+        buffer[waveform.len()..].fill(0.);
+        self.execute();
+        spectrum.copy_from_slice(self.spectrum());
+        Ok(())
+    }
+}
+
+impl Drop for ForwardRealFFT {
+    fn drop(&mut self) {
+        unsafe { DestroyForwardRealFFT(&mut self.inner) }
+    }
+}
+
+/// Inverse FFT from a spectrum of `fft_size / 2 + 1` bins to a real waveform of `fft_size` samples.
+///
+/// Same as WORLD, the output is not normalized, i.e. it is `fft_size` times of the inverse DFT.
+pub struct InverseRealFFT {
+    inner: world_sys::InverseRealFFT,
+}
+
+// the buffers and the plan are owned only by this value
+unsafe impl Send for InverseRealFFT {}
+
+impl InverseRealFFT {
+    /// # Panics
+    /// Panics if `fft_size` is not a power of two or is smaller than 4.
+    pub fn new(fft_size: usize) -> InverseRealFFT {
+        InverseRealFFT::try_new(fft_size).unwrap()
+    }
+
+    pub fn try_new(fft_size: usize) -> Result<InverseRealFFT, WorldError> {
+        let fft_size = validate_fft_size(fft_size)?;
+        let inner = unsafe {
+            let mut inner = MaybeUninit::uninit();
+            InitializeInverseRealFFT(fft_size, inner.as_mut_ptr());
+            inner.assume_init()
+        };
+        Ok(InverseRealFFT { inner })
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.inner.fft_size as usize
+    }
+
+    /// Input buffer of `fft_size / 2 + 1` bins.
+    pub fn spectrum(&self) -> &[[f64; 2]] {
+        unsafe { slice::from_raw_parts(self.inner.spectrum, self.fft_size() / 2 + 1) }
+    }
+
+    pub fn spectrum_mut(&mut self) -> &mut [[f64; 2]] {
+        unsafe { slice::from_raw_parts_mut(self.inner.spectrum, self.fft_size() / 2 + 1) }
+    }
+
+    /// Output buffer of `fft_size` samples, which is updated by [`InverseRealFFT::execute`].
+    pub fn waveform(&self) -> &[f64] {
+        unsafe { slice::from_raw_parts(self.inner.waveform, self.fft_size()) }
+    }
+
+    /// Transform the spectrum buffer into the waveform buffer.
+    pub fn execute(&mut self) {
+        unsafe { fft_execute(ptr::read(&self.inner.inverse_fft)) }
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const world_sys::InverseRealFFT {
+        &self.inner
+    }
+
+    /// Transform `spectrum` of `fft_size / 2 + 1` bins into `waveform` of `fft_size` samples.
+    pub fn process(&mut self, spectrum: &[[f64; 2]], waveform: &mut [f64]) -> Result<(), WorldError> {
+        validate_length(self.fft_size() / 2 + 1, spectrum.len())?;
+        validate_length(self.fft_size(), waveform.len())?;
+        self.spectrum_mut().copy_from_slice(spectrum);
+        self.execute();
+        waveform.copy_from_slice(self.waveform());
+        Ok(())
+    }
+}
+
+impl Drop for InverseRealFFT {
+    fn drop(&mut self) {
+        unsafe { DestroyInverseRealFFT(&mut self.inner) }
+    }
+}
+
+/// Calculation of the minimum phase spectrum from a log amplitude spectrum, which is used in the synthesis of WORLD.
+pub struct MinimumPhaseAnalysis {
+    inner: world_sys::MinimumPhaseAnalysis,
+}
+
+// the buffers and the plans are owned only by this value
+unsafe impl Send for MinimumPhaseAnalysis {}
+
+impl MinimumPhaseAnalysis {
+    /// # Panics
+    /// Panics if `fft_size` is not a power of two or is smaller than 4.
+    pub fn new(fft_size: usize) -> MinimumPhaseAnalysis {
+        MinimumPhaseAnalysis::try_new(fft_size).unwrap()
+    }
+
+    pub fn try_new(fft_size: usize) -> Result<MinimumPhaseAnalysis, WorldError> {
+        let fft_size = validate_fft_size(fft_size)?;
+        let inner = unsafe {
+            let mut inner = MaybeUninit::uninit();
+            InitializeMinimumPhaseAnalysis(fft_size, inner.as_mut_ptr());
+            inner.assume_init()
+        };
+        Ok(MinimumPhaseAnalysis { inner })
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.inner.fft_size as usize
+    }
+
+    /// Input buffer of the natural log of the amplitude spectrum in `fft_size / 2 + 1` bins.
+    pub fn log_spectrum(&self) -> &[f64] {
+        unsafe { slice::from_raw_parts(self.inner.log_spectrum, self.fft_size() / 2 + 1) }
+    }
+
+    pub fn log_spectrum_mut(&mut self) -> &mut [f64] {
+        unsafe { slice::from_raw_parts_mut(self.inner.log_spectrum, self.fft_size() / 2 + 1) }
+    }
+
+    /// Output buffer of `fft_size / 2 + 1` bins, which is updated by [`MinimumPhaseAnalysis::execute`].
+    pub fn minimum_phase_spectrum(&self) -> &[[f64; 2]] {
+        unsafe { slice::from_raw_parts(self.inner.minimum_phase_spectrum, self.fft_size() / 2 + 1) }
+    }
+
+    /// Calculate the minimum phase spectrum from the log spectrum buffer.
+    pub fn execute(&mut self) {
+        unsafe { GetMinimumPhaseSpectrum(&self.inner) }
+    }
+
+    /// Calculate the minimum phase spectrum of `log_spectrum` into `spectrum`, both of `fft_size / 2 + 1` bins.
+    pub fn process(&mut self, log_spectrum: &[f64], spectrum: &mut [[f64; 2]]) -> Result<(), WorldError> {
+        validate_length(self.fft_size() / 2 + 1, log_spectrum.len())?;
+        validate_length(self.fft_size() / 2 + 1, spectrum.len())?;
+        self.log_spectrum_mut().copy_from_slice(log_spectrum);
+        self.execute();
+        spectrum.copy_from_slice(self.minimum_phase_spectrum());
+        Ok(())
+    }
+}
+
+impl Drop for MinimumPhaseAnalysis {
+    fn drop(&mut self) {
+        unsafe { DestroyMinimumPhaseAnalysis(&mut self.inner) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_fft_size() {
+        assert_eq!(validate_fft_size(1024), Ok(1024));
+        assert_eq!(validate_fft_size(4), Ok(4));
+        assert_eq!(validate_fft_size(2), Err(WorldError::InvalidFFTSize(2)));
+        assert_eq!(validate_fft_size(1000), Err(WorldError::InvalidFFTSize(1000)));
+        assert!(ForwardRealFFT::try_new(0).is_err());
+    }
+
+    #[test]
+    fn test_real_fft() {
+        const FFT_SIZE: usize = 16;
+        let waveform = (0..12).map(|i| (i as f64 * 0.7).sin() + 0.1).collect::<Vec<_>>();
+        let mut forward = ForwardRealFFT::new(FFT_SIZE);
+        let mut spectrum = [[0.; 2]; FFT_SIZE / 2 + 1];
+        forward.process(&waveform, &mut spectrum).unwrap();
+        assert!((spectrum[0][0] - waveform.iter().sum::<f64>()).abs() < 1e-9);
+        assert!(spectrum[0][1].abs() < 1e-9);
+
+        let mut inverse = InverseRealFFT::new(FFT_SIZE);
+        let mut restored = [0.; FFT_SIZE];
+        inverse.process(&spectrum, &mut restored).unwrap();
+        for (i, restored) in restored.iter().enumerate() {
+            let expected = waveform.get(i).copied().unwrap_or(0.);
+            assert!((restored / FFT_SIZE as f64 - expected).abs() < 1e-9);
+        }
+
+        assert_eq!(
+            forward.process(&[0.; FFT_SIZE + 1], &mut spectrum),
+            Err(WorldError::TooLongSignal(FFT_SIZE + 1))
+        );
+        assert_eq!(
+            inverse.process(&spectrum[1..], &mut restored),
+            Err(WorldError::DifferentLength {
+                expected: FFT_SIZE / 2 + 1,
+                actual: FFT_SIZE / 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_minimum_phase_impulse_response() {
+        const FFT_SIZE: usize = 64;
+        // 1 - 0.5z^-1 is minimum phase, so it is restored from its amplitude
+        let mut forward = ForwardRealFFT::new(FFT_SIZE);
+        let mut spectrum = [[0.; 2]; FFT_SIZE / 2 + 1];
+        forward.process(&[1., -0.5], &mut spectrum).unwrap();
+        let log_spectrum = spectrum.iter().map(|[re, im]| re.hypot(*im).ln()).collect::<Vec<_>>();
+
+        let mut minimum_phase = MinimumPhaseAnalysis::new(FFT_SIZE);
+        let mut minimum_phase_spectrum = [[0.; 2]; FFT_SIZE / 2 + 1];
+        minimum_phase.process(&log_spectrum, &mut minimum_phase_spectrum).unwrap();
+        let mut inverse = InverseRealFFT::new(FFT_SIZE);
+        let mut response = [0.; FFT_SIZE];
+        inverse.process(&minimum_phase_spectrum, &mut response).unwrap();
+        for (i, response) in response.iter().enumerate() {
+            let expected = [1., -0.5].get(i).copied().unwrap_or(0.);
+            assert!((response / FFT_SIZE as f64 - expected).abs() < 1e-6, "{i}: {response}");
+        }
+    }
+}
@@ -1,6 +1,22 @@
 pub use world_sys;
 
+pub mod codec;
+pub mod cross_synthesis;
+pub mod dtw;
+pub mod dynamic_features;
+pub mod error;
+pub mod fft;
+pub mod matlab;
+pub mod mel_cepstrum;
+pub mod mel_spectrogram;
+pub mod metrics;
+pub mod morphing;
+#[cfg(feature = "npy")]
+pub mod npy;
 pub mod options;
+pub mod resample;
+pub mod sanitize;
 pub mod signal_analyzer;
 pub mod spectrogram_like;
+pub mod sptk;
 pub mod synthesis;
@@ -0,0 +1,236 @@
+//! Safe wrappers of the MATLAB-compatible utilities in matlabfunctions.cpp,
+//! which give exactly the same numerics as the analyses of WORLD.
+//!
+//! original: <https://github.com/mmorise/World/tree/v1.0.0/src/matlabfunctions.cpp>
+
+use std::os::raw::c_int;
+
+use world_sys::{
+    decimate as world_decimate, diff as world_diff, fast_fftfilt as world_fast_fftfilt, fftshift as world_fftshift, histc as world_histc,
+    interp1 as world_interp1, interp1Q, inv as world_inv,
+};
+
+use crate::error::WorldError;
+use crate::fft::{ForwardRealFFT, InverseRealFFT};
+
+/// Length of the reflected padding on both ends in `decimate`.
+const DECIMATE_PADDING: usize = 9;
+/// Decimation ratios which `decimate` has the filter coefficients for.
+const DECIMATE_RATIOS: std::ops::RangeInclusive<usize> = 2..=12;
+
+fn length(x: &[f64]) -> Result<c_int, WorldError> {
+    c_int::try_from(x.len()).map_err(|_| WorldError::TooLongSignal(x.len()))
+}
+
+/// Swap the first and the second half of `x`, whose length must be even.
+pub fn fftshift(x: &[f64]) -> Result<Vec<f64>, WorldError> {
+    let x_length = length(x)?;
+    if !x.len().is_multiple_of(2) {
+        return Err(WorldError::InvalidParameter("length of x"));
+    }
+    let mut y = vec![0.; x.len()];
+    unsafe { world_fftshift(x.as_ptr(), x_length, y.as_mut_ptr()) };
+    Ok(y)
+}
+
+/// Differences between the adjacent elements, `x[i + 1] - x[i]`.
+pub fn diff(x: &[f64]) -> Result<Vec<f64>, WorldError> {
+    let x_length = length(x)?;
+    if x.len() < 2 {
+        return Ok(Vec::new());
+    }
+    let mut y = vec![0.; x.len() - 1];
+    unsafe { world_diff(x.as_ptr(), x_length, y.as_mut_ptr()) };
+    Ok(y)
+}
+
+/// Index of the bin `x[i]..x[i + 1]` which contains each of `edges`.
+///
+/// `x` must be sorted in ascending order and have two or more elements, and `edges` must also be sorted.
+/// Values out of `x` are counted in the first or the last bin, and indices are 0-based unlike `histc` in WORLD.
+pub fn histc(x: &[f64], edges: &[f64]) -> Result<Vec<usize>, WorldError> {
+    let x_length = length(x)?;
+    let edges_length = length(edges)?;
+    if x.len() < 2 {
+        return Err(WorldError::InvalidParameter("length of x"));
+    }
+    let mut index = vec![0; edges.len()];
+    unsafe { world_histc(x.as_ptr(), x_length, edges.as_ptr(), edges_length, index.as_mut_ptr()) };
+    Ok(index.into_iter().map(|index| index as usize - 1).collect())
+}
+
+/// Linear interpolation of `y` sampled at `x` into `xi`.
+///
+/// `x` must be sorted in ascending order and have two or more elements, and `xi` must also be sorted.
+/// Values out of `x` are extrapolated from the first or the last two points.
+pub fn interp1(x: &[f64], y: &[f64], xi: &[f64]) -> Result<Vec<f64>, WorldError> {
+    let x_length = length(x)?;
+    let xi_length = length(xi)?;
+    if x.len() != y.len() {
+        return Err(WorldError::DifferentLength {
+            expected: x.len(),
+            actual: y.len(),
+        });
+    }
+    if x.len() < 2 {
+        return Err(WorldError::InvalidParameter("length of x"));
+    }
+    let mut yi = vec![0.; xi.len()];
+    unsafe { world_interp1(x.as_ptr(), y.as_ptr(), x_length, xi.as_ptr(), xi_length, yi.as_mut_ptr()) };
+    Ok(yi)
+}
+
+/// Linear interpolation of `y` sampled at `x0 + shift * i` into `xi`.
+///
+/// `y` must not be empty, and every `xi` must satisfy `0 <= (xi - x0) / shift < y.len()` after truncation toward zero.
+pub fn interp1q(x0: f64, shift: f64, y: &[f64], xi: &[f64]) -> Result<Vec<f64>, WorldError> {
+    let x_length = length(y)?;
+    let xi_length = length(xi)?;
+    // interp1Q writes the last difference of y even if xi is empty
+    if y.is_empty() {
+        return Err(WorldError::InvalidParameter("y"));
+    }
+    if !x0.is_finite() || !shift.is_finite() || shift == 0. {
+        return Err(WorldError::InvalidParameter("x0 or shift"));
+    }
+    // same index as interp1Q, which does not check the range
+    let in_range = |xi: f64| {
+        let position = (xi - x0) / shift;
+        position.is_finite() && position.trunc() >= 0. && position.trunc() < y.len() as f64
+    };
+    if !xi.iter().all(|&xi| in_range(xi)) {
+        return Err(WorldError::InvalidParameter("xi"));
+    }
+    let mut yi = vec![0.; xi.len()];
+    unsafe { interp1Q(x0, shift, y.as_ptr(), x_length, xi.as_ptr(), xi_length, yi.as_mut_ptr()) };
+    Ok(yi)
+}
+
+/// Lowpass filter `x` forward and backward, then take every `r`-th sample, which gives `(x.len() - 1) / r + 1` samples.
+///
+/// `r` must be in 2..=12, and `x` must have 10 or more samples for the reflected padding.
+pub fn decimate(x: &[f64], r: usize) -> Result<Vec<f64>, WorldError> {
+    let x_length = length(x)?;
+    if !DECIMATE_RATIOS.contains(&r) {
+        return Err(WorldError::InvalidParameter("r"));
+    }
+    if x.len() <= DECIMATE_PADDING {
+        return Err(WorldError::InvalidParameter("length of x"));
+    }
+    let mut y = vec![0.; (x.len() - 1) / r + 1];
+    unsafe { world_decimate(x.as_ptr(), x_length, r as c_int, y.as_mut_ptr()) };
+    Ok(y)
+}
+
+/// Linear convolution of `x` and `h` via FFT, which gives `x.len() + h.len() - 1` samples.
+///
+/// Both plans must have the same fft size of at least the length of the output.
+pub fn fast_fftfilt(
+    x: &[f64],
+    h: &[f64],
+    forward_real_fft: &mut ForwardRealFFT,
+    inverse_real_fft: &mut InverseRealFFT,
+) -> Result<Vec<f64>, WorldError> {
+    let x_length = length(x)?;
+    let h_length = length(h)?;
+    if x.is_empty() {
+        return Err(WorldError::EmptySignal);
+    }
+    if h.is_empty() {
+        return Err(WorldError::InvalidParameter("length of h"));
+    }
+    let fft_size = forward_real_fft.fft_size();
+    if fft_size != inverse_real_fft.fft_size() || fft_size < x.len() + h.len() - 1 {
+        return Err(WorldError::InvalidFFTSize(fft_size as i32));
+    }
+    let mut y = vec![0.; fft_size];
+    unsafe {
+        world_fast_fftfilt(
+            x.as_ptr(),
+            x_length,
+            h.as_ptr(),
+            h_length,
+            fft_size as c_int,
+            forward_real_fft.as_ptr(),
+            inverse_real_fft.as_ptr(),
+            y.as_mut_ptr(),
+        )
+    };
+    y.truncate(x.len() + h.len() - 1);
+    Ok(y)
+}
+
+/// Inverse of the `n` x `n` matrix in row-major order.
+///
+/// Same as WORLD, Gauss-Jordan elimination without pivoting is used,
+/// so a zero on the diagonal during the elimination gives non-finite values.
+pub fn inv(matrix: &[f64], n: usize) -> Result<Vec<f64>, WorldError> {
+    if n.checked_mul(n) != Some(matrix.len()) {
+        return Err(WorldError::InvalidParameter("size of matrix"));
+    }
+    let n_int = c_int::try_from(n).map_err(|_| WorldError::InvalidParameter("size of matrix"))?;
+    if n == 0 {
+        return Ok(Vec::new());
+    }
+    // inv overwrites the input matrix
+    let mut r = matrix.to_vec();
+    let mut invr = vec![0.; matrix.len()];
+    let mut r_lines = r.chunks_mut(n).map(<[f64]>::as_mut_ptr).collect::<Vec<_>>();
+    let mut invr_lines = invr.chunks_mut(n).map(<[f64]>::as_mut_ptr).collect::<Vec<_>>();
+    unsafe { world_inv(r_lines.as_mut_ptr(), n_int, invr_lines.as_mut_ptr()) };
+    Ok(invr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: &[f64], actual: &[f64]) {
+        assert_eq!(expected.len(), actual.len());
+        for (expected, actual) in expected.iter().zip(actual) {
+            assert!((expected - actual).abs() < 1e-9, "{expected} != {actual}");
+        }
+    }
+
+    #[test]
+    fn test_matlab_functions() {
+        assert_eq!(fftshift(&[1., 2., 3., 4.]).unwrap(), [3., 4., 1., 2.]);
+        assert_eq!(fftshift(&[1., 2., 3.]), Err(WorldError::InvalidParameter("length of x")));
+        assert_eq!(diff(&[1., 4., 9.]).unwrap(), [3., 5.]);
+        assert!(diff(&[1.]).unwrap().is_empty());
+        assert_eq!(histc(&[0., 1., 2.], &[-1., 0., 0.5, 1.5, 3.]).unwrap(), [0, 0, 0, 1, 1]);
+
+        let x = [0., 1., 3.];
+        let y = [0., 2., 0.];
+        assert_close(&interp1(&x, &y, &[-1., 0.5, 2., 4.]).unwrap(), &[-2., 1., 1., -1.]);
+        assert_eq!(interp1(&x, &y[1..], &[0.]), Err(WorldError::DifferentLength { expected: 3, actual: 2 }));
+        assert_close(&interp1q(1., 0.5, &[0., 1., 4.], &[1.25, 1.5, 1.75]).unwrap(), &[0.5, 1., 2.5]);
+        assert_eq!(interp1q(1., 0.5, &[0., 1., 4.], &[0.]), Err(WorldError::InvalidParameter("xi")));
+        assert_eq!(interp1q(1., 0.5, &[0., 1., 4.], &[2.5]), Err(WorldError::InvalidParameter("xi")));
+        assert_eq!(interp1q(1., 0.5, &[], &[]), Err(WorldError::InvalidParameter("y")));
+
+        let mut forward = ForwardRealFFT::new(8);
+        let mut inverse = InverseRealFFT::new(8);
+        assert_close(
+            &fast_fftfilt(&[1., 2., 3.], &[1., -1.], &mut forward, &mut inverse).unwrap(),
+            &[1., 1., 1., -3.],
+        );
+        assert_eq!(
+            fast_fftfilt(&[1.; 8], &[1., -1.], &mut forward, &mut inverse),
+            Err(WorldError::InvalidFFTSize(8))
+        );
+
+        assert_close(&inv(&[2., 1., 1., 1.], 2).unwrap(), &[1., -1., -1., 2.]);
+        assert_eq!(inv(&[1., 2., 3.], 2), Err(WorldError::InvalidParameter("size of matrix")));
+    }
+
+    #[test]
+    fn test_decimate() {
+        let y = decimate(&[1.; 1000], 4).unwrap();
+        assert_eq!(y.len(), 250);
+        // the filter has the unit gain at DC
+        assert!(y[50..200].iter().all(|y| (y - 1.).abs() < 1e-3));
+        assert_eq!(decimate(&[1.; 1000], 1), Err(WorldError::InvalidParameter("r")));
+        assert_eq!(decimate(&[1.; 9], 2), Err(WorldError::InvalidParameter("length of x")));
+    }
+}
@@ -0,0 +1,167 @@
+//! Conversion between the spectral envelope and the mel-cepstrum of SPTK, i.e. the cepstrum warped by an all-pass filter.
+//!
+//! The conversion is the same as `sp2mc` and `mc2sp` of pysptk, which uses the frequency transform of the cepstrum
+//! instead of the iterative mel-cepstral analysis. Unlike `CodeSpectralEnvelope` of WORLD in [`crate::codec`],
+//! the coefficients are compatible with SPTK.
+
+use crate::codec::fft_size_of;
+use crate::error::WorldError;
+use crate::fft::{ForwardRealFFT, InverseRealFFT};
+use crate::spectrogram_like::SpectrogramLike;
+
+/// Frequency transform of the cepstrum `c1` into the cepstrum of `order` warped by the all-pass constant `alpha`,
+/// same as `freqt` of SPTK.
+fn freqt(c1: &[f64], order: usize, alpha: f64) -> Vec<f64> {
+    let beta = 1. - alpha * alpha;
+    let mut g = vec![0.; order + 1];
+    let mut d = vec![0.; order + 1];
+    for &c in c1.iter().rev() {
+        d.copy_from_slice(&g);
+        g[0] = c + alpha * d[0];
+        if order >= 1 {
+            g[1] = beta * d[0] + alpha * d[1];
+        }
+        for j in 2..=order {
+            g[j] = d[j - 1] + alpha * (d[j] - g[j - 1]);
+        }
+    }
+    g
+}
+
+/// Number of the points to compare the mel scale and the warped frequency in [`alpha_for_fs`].
+const ALPHA_SEARCH_POINTS: usize = 1000;
+/// Step of the candidates of alpha in [`alpha_for_fs`].
+const ALPHA_SEARCH_STEP: f64 = 0.001;
+
+/// All-pass constant whose frequency warping is the closest to the mel scale at `fs`, same as `mcepalpha` of pysptk,
+/// e.g. 0.41 for 16 kHz, 0.455 for 22.05 kHz and 0.554 for 48 kHz.
+pub fn alpha_for_fs(fs: u32) -> f64 {
+    let normalize = |values: Vec<f64>| {
+        let last = values[values.len() - 1];
+        values.into_iter().map(move |value| value / last)
+    };
+    let step = fs as f64 / 2. / ALPHA_SEARCH_POINTS as f64;
+    let mel_scale = (0..ALPHA_SEARCH_POINTS).map(|i| (1. + step * i as f64 / 1000.).ln()).collect();
+    let mel_scale = normalize(mel_scale).collect::<Vec<_>>();
+    let distance = |alpha: f64| {
+        let warped = (0..ALPHA_SEARCH_POINTS)
+            .map(|i| {
+                let omega = std::f64::consts::PI * i as f64 / ALPHA_SEARCH_POINTS as f64;
+                let num = (1. - alpha * alpha) * omega.sin();
+                let den = (1. + alpha * alpha) * omega.cos() - 2. * alpha;
+                num.atan2(den)
+            })
+            .collect();
+        normalize(warped).zip(&mel_scale).map(|(warped, mel)| (warped - mel).powi(2)).sum::<f64>()
+    };
+    (0..(1. / ALPHA_SEARCH_STEP) as usize)
+        .map(|i| i as f64 * ALPHA_SEARCH_STEP)
+        .map(|alpha| (alpha, distance(alpha)))
+        .fold((0., f64::INFINITY), |best, candidate| if candidate.1 < best.1 { candidate } else { best })
+        .0
+}
+
+fn validate_alpha(alpha: f64) -> Result<(), WorldError> {
+    if alpha.is_finite() && alpha.abs() < 1. {
+        Ok(())
+    } else {
+        Err(WorldError::InvalidParameter("alpha"))
+    }
+}
+
+/// Mel-cepstrum of `order` (i.e. `order + 1` coefficients per frame) of the power spectrogram, warped by `alpha` in (-1, 1).
+///
+/// The spectrogram must have `fft_size / 2 + 1` bins of a power of two `fft_size`, and positive power.
+/// `alpha` of 0 gives the plain cepstrum, and [`alpha_for_fs`] gives the usual value for the sample rate.
+pub fn spectrogram_to_mel_cepstrum(spectrogram: &SpectrogramLike<f64>, order: usize, alpha: f64) -> Result<SpectrogramLike<f64>, WorldError> {
+    validate_alpha(alpha)?;
+    let fft_size = fft_size_of(spectrogram.frequency_axis_size())? as usize;
+    let mut inverse_real_fft = InverseRealFFT::try_new(fft_size)?;
+    let mut mel_cepstrum = SpectrogramLike::try_new(spectrogram.time_axis_size(), order + 1)?;
+    let mut cepstrum = vec![0.; fft_size];
+    for (power, mel_cepstrum) in spectrogram.lines().zip(mel_cepstrum.lines_mut()) {
+        let log_power = power.iter().map(|&power| [power.ln(), 0.]).collect::<Vec<_>>();
+        inverse_real_fft.process(&log_power, &mut cepstrum)?;
+        // same as pysptk, the whole cepstrum including the negative quefrencies goes into freqt
+        let mut c = cepstrum.iter().map(|c| c / fft_size as f64).collect::<Vec<_>>();
+        c[0] /= 2.;
+        mel_cepstrum.copy_from_slice(&freqt(&c, order, alpha));
+    }
+    Ok(mel_cepstrum)
+}
+
+/// Power spectrogram of `fft_size / 2 + 1` bins from the mel-cepstrum made by [`spectrogram_to_mel_cepstrum`] with the same `alpha`.
+///
+/// The envelope is smoothed by the truncation to the order of the mel-cepstrum.
+pub fn mel_cepstrum_to_spectrogram(mel_cepstrum: &SpectrogramLike<f64>, alpha: f64, fft_size: usize) -> Result<SpectrogramLike<f64>, WorldError> {
+    validate_alpha(alpha)?;
+    let mut forward_real_fft = ForwardRealFFT::try_new(fft_size)?;
+    let mut spectrogram = SpectrogramLike::try_new(mel_cepstrum.time_axis_size(), fft_size / 2 + 1)?;
+    let mut symmetric = vec![0.; fft_size];
+    let mut spectrum = vec![[0.; 2]; fft_size / 2 + 1];
+    for (mel_cepstrum, power) in mel_cepstrum.lines().zip(spectrogram.lines_mut()) {
+        let c = freqt(mel_cepstrum, fft_size / 2, -alpha);
+        symmetric[0] = 2. * c[0];
+        for m in 1..=fft_size / 2 {
+            symmetric[m] = c[m];
+            symmetric[fft_size - m] = c[m];
+        }
+        forward_real_fft.process(&symmetric, &mut spectrum)?;
+        for (power, [log_power, _]) in power.iter_mut().zip(&spectrum) {
+            *power = log_power.exp();
+        }
+    }
+    Ok(spectrogram)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut spectrogram = SpectrogramLike::new(1, 257);
+        for (bin, power) in spectrogram.lines_mut().flatten().enumerate() {
+            *power = 1. + (bin as f64 / 40.).cos().powi(2);
+        }
+        let mel_cepstrum = spectrogram_to_mel_cepstrum(&spectrogram, 40, 0.42).unwrap();
+        let restored = mel_cepstrum_to_spectrogram(&mel_cepstrum, 0.42, 512).unwrap();
+        for (expected, actual) in spectrogram.lines().flatten().zip(restored.lines().flatten()) {
+            assert!((actual / expected).ln().abs() < 0.05, "{expected} != {actual}");
+        }
+    }
+
+    #[test]
+    fn test_first_order_system() {
+        // 1 / (1 - a z^-1) has the mel-cepstrum -ln(1 - a alpha), ((a - alpha) / (1 - a alpha))^m / m - (-alpha)^m / m
+        let (a, alpha, fft_size) = (0.5, 0.41, 1024);
+        let power = |omega: f64| 1. / (1. - 2. * a * omega.cos() + a * a);
+        let spectrogram = SpectrogramLike::from_fn(1, fft_size / 2 + 1, |_, bin| power(std::f64::consts::TAU * bin as f64 / fft_size as f64));
+        let b = (a - alpha) / (1. - a * alpha);
+        let expected = (0..=24)
+            .map(|m| match m {
+                0 => -(1. - a * alpha).ln(),
+                m => (b.powi(m) - (-alpha).powi(m)) / m as f64,
+            })
+            .collect::<Vec<_>>();
+        let mel_cepstrum = spectrogram_to_mel_cepstrum(&spectrogram, 24, alpha).unwrap();
+        for (expected, actual) in expected.iter().zip(mel_cepstrum.line(0).unwrap()) {
+            assert!((expected - actual).abs() < 1e-9, "{expected} != {actual}");
+        }
+        let restored = mel_cepstrum_to_spectrogram(&mel_cepstrum, alpha, fft_size).unwrap();
+        for (expected, actual) in spectrogram.lines().flatten().zip(restored.lines().flatten()) {
+            assert!((actual / expected).ln().abs() < 1e-6, "{expected} != {actual}");
+        }
+    }
+
+    #[test]
+    fn test_alpha_for_fs() {
+        assert!((alpha_for_fs(16000) - 0.41).abs() < 1e-9);
+        assert!((alpha_for_fs(22050) - 0.455).abs() < 1e-9);
+        assert!((alpha_for_fs(48000) - 0.554).abs() < 1e-9);
+        assert_eq!(
+            spectrogram_to_mel_cepstrum(&SpectrogramLike::new(1, 257), 24, 1.).err(),
+            Some(WorldError::InvalidParameter("alpha"))
+        );
+    }
+}
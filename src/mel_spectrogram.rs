@@ -0,0 +1,279 @@
+//! Log-mel filterbank features for neural vocoders, from the spectral envelope or from the signal.
+//!
+//! The filterbank is the same as `librosa.filters.mel` with the default Slaney mel scale and normalization,
+//! and the features are the natural log of the filtered amplitude, same as HiFi-GAN and its successors.
+
+use std::f64::consts::PI;
+
+use crate::error::{validate_fs, validate_signal, WorldError};
+use crate::fft::ForwardRealFFT;
+use crate::spectrogram_like::SpectrogramLike;
+
+/// Smallest value before the log, same as HiFi-GAN.
+pub const DEFAULT_LOG_FLOOR: f64 = 1e-5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MelOption {
+    pub n_mels: usize,
+    /// Lowest frequency of the filterbank in Hz.
+    pub fmin: f64,
+    /// Highest frequency of the filterbank in Hz, or the Nyquist frequency if `None`.
+    pub fmax: Option<f64>,
+    /// Values below this are raised to it before the log.
+    pub log_floor: f64,
+}
+
+impl MelOption {
+    pub fn new(n_mels: usize) -> MelOption {
+        MelOption {
+            n_mels,
+            fmin: 0.,
+            fmax: None,
+            log_floor: DEFAULT_LOG_FLOOR,
+        }
+    }
+}
+
+impl Default for MelOption {
+    fn default() -> Self {
+        MelOption::new(80)
+    }
+}
+
+// Slaney mel scale, linear below 1 kHz and logarithmic above
+const F_SP: f64 = 200. / 3.;
+const MIN_LOG_HZ: f64 = 1000.;
+const MIN_LOG_MEL: f64 = MIN_LOG_HZ / F_SP;
+
+fn log_step() -> f64 {
+    6.4f64.ln() / 27.
+}
+
+fn hz_to_mel(hz: f64) -> f64 {
+    if hz < MIN_LOG_HZ {
+        hz / F_SP
+    } else {
+        MIN_LOG_MEL + (hz / MIN_LOG_HZ).ln() / log_step()
+    }
+}
+
+fn mel_to_hz(mel: f64) -> f64 {
+    if mel < MIN_LOG_MEL {
+        mel * F_SP
+    } else {
+        MIN_LOG_HZ * ((mel - MIN_LOG_MEL) * log_step()).exp()
+    }
+}
+
+/// Triangular filters over the bins of a spectrum of `fft_size / 2 + 1` bins.
+pub struct MelFilterbank {
+    /// `n_mels` lines of the weights of `fft_size / 2 + 1` bins
+    weights: SpectrogramLike<f64>,
+    log_floor: f64,
+}
+
+impl MelFilterbank {
+    pub fn new(fs: u32, fft_size: usize, option: &MelOption) -> MelFilterbank {
+        MelFilterbank::try_new(fs, fft_size, option).unwrap()
+    }
+
+    /// `fmin` and `fmax` must satisfy `0 <= fmin < fmax <= fs / 2`.
+    pub fn try_new(fs: u32, fft_size: usize, option: &MelOption) -> Result<MelFilterbank, WorldError> {
+        validate_fs(fs)?;
+        let nyquist = fs as f64 / 2.;
+        let fmax = option.fmax.unwrap_or(nyquist);
+        if !(option.fmin >= 0. && option.fmin < fmax && fmax <= nyquist) {
+            return Err(WorldError::InvalidParameter("fmin or fmax"));
+        }
+        if !(option.log_floor > 0. && option.log_floor.is_finite()) {
+            return Err(WorldError::InvalidParameter("log_floor"));
+        }
+        if fft_size < 2 {
+            return Err(WorldError::InvalidFFTSize(fft_size.try_into().unwrap_or(i32::MAX)));
+        }
+        let mut weights = SpectrogramLike::try_new(option.n_mels, fft_size / 2 + 1)?;
+
+        let (mel_min, mel_max) = (hz_to_mel(option.fmin), hz_to_mel(fmax));
+        let mel_frequencies = (0..option.n_mels + 2)
+            .map(|i| mel_to_hz(mel_min + (mel_max - mel_min) * i as f64 / (option.n_mels + 1) as f64))
+            .collect::<Vec<_>>();
+        for (i, weights) in weights.lines_mut().enumerate() {
+            let [lower, center, upper] = [mel_frequencies[i], mel_frequencies[i + 1], mel_frequencies[i + 2]];
+            // area normalization of Slaney
+            let normalization = 2. / (upper - lower);
+            for (bin, weight) in weights.iter_mut().enumerate() {
+                let frequency = bin as f64 * fs as f64 / fft_size as f64;
+                let rising = (frequency - lower) / (center - lower);
+                let falling = (upper - frequency) / (upper - center);
+                *weight = rising.min(falling).max(0.) * normalization;
+            }
+        }
+        Ok(MelFilterbank {
+            weights,
+            log_floor: option.log_floor,
+        })
+    }
+
+    pub fn n_mels(&self) -> usize {
+        self.weights.time_axis_size()
+    }
+
+    /// Number of the bins of the input spectrum, `fft_size / 2 + 1`.
+    pub fn frequency_axis_size(&self) -> usize {
+        self.weights.frequency_axis_size()
+    }
+
+    /// Weights of the `mel`-th filter for each bin.
+    pub fn weights(&self, mel: usize) -> Option<&[f64]> {
+        self.weights.line(mel)
+    }
+
+    /// Log-mel features of an amplitude spectrum into `out` of `n_mels` values.
+    pub fn process(&self, amplitude: &[f64], out: &mut [f64]) -> Result<(), WorldError> {
+        for (expected, actual) in [(self.frequency_axis_size(), amplitude.len()), (self.n_mels(), out.len())] {
+            if expected != actual {
+                return Err(WorldError::DifferentLength { expected, actual });
+            }
+        }
+        for (out, weights) in out.iter_mut().zip(self.weights.lines()) {
+            let mel = weights.iter().zip(amplitude).map(|(weight, amplitude)| weight * amplitude).sum::<f64>();
+            *out = mel.max(self.log_floor).ln();
+        }
+        Ok(())
+    }
+}
+
+/// Log-mel features of each frame of the power spectral envelope such as [`crate::signal_analyzer::AnalyzeResult::spectrogram`].
+///
+/// The scale differs from [`mel_spectrogram_from_signal`], because the envelope of CheapTrick is normalized
+/// independently of the window.
+pub fn mel_spectrogram_from_envelope(spectrogram: &SpectrogramLike<f64>, fs: u32, option: &MelOption) -> Result<SpectrogramLike<f64>, WorldError> {
+    let fft_size = (spectrogram.frequency_axis_size() - 1) * 2;
+    let filterbank = MelFilterbank::try_new(fs, fft_size, option)?;
+    let mut mel_spectrogram = SpectrogramLike::try_new(spectrogram.time_axis_size(), option.n_mels)?;
+    let mut amplitude = vec![0.; spectrogram.frequency_axis_size()];
+    for (power, out) in spectrogram.lines().zip(mel_spectrogram.lines_mut()) {
+        for (amplitude, power) in amplitude.iter_mut().zip(power) {
+            *amplitude = power.max(0.).sqrt();
+        }
+        filterbank.process(&amplitude, out)?;
+    }
+    Ok(mel_spectrogram)
+}
+
+/// Log-mel features of the signal at the frames of `temporal_positions` in seconds, which are given by the analyses,
+/// so that the features are aligned with f0, the spectral envelope and the aperiodicity.
+/// The positions must be within the duration of the signal.
+///
+/// Each frame is the amplitude spectrum of `fft_size` samples centered at its position with the Hann window,
+/// and the signal is reflected at both ends, same as `center=True` of librosa.
+pub fn mel_spectrogram_from_signal(
+    signal: &[f64],
+    fs: u32,
+    temporal_positions: &[f64],
+    fft_size: usize,
+    option: &MelOption,
+) -> Result<SpectrogramLike<f64>, WorldError> {
+    validate_signal(signal)?;
+    let filterbank = MelFilterbank::try_new(fs, fft_size, option)?;
+    let mut forward_real_fft = ForwardRealFFT::try_new(fft_size)?;
+    let duration = signal.len() as f64 / fs as f64;
+    if !temporal_positions.iter().all(|t| (0. ..=duration).contains(t)) {
+        return Err(WorldError::InvalidParameter("temporal_positions"));
+    }
+    let mut mel_spectrogram = SpectrogramLike::try_new(temporal_positions.len(), option.n_mels)?;
+    // periodic Hann window, same as scipy.signal.get_window("hann")
+    let window = (0..fft_size)
+        .map(|n| 0.5 - 0.5 * (2. * PI * n as f64 / fft_size as f64).cos())
+        .collect::<Vec<_>>();
+    let last = signal.len() as isize - 1;
+    let reflect = |index: isize| {
+        let index = if index < 0 { -index } else { index };
+        let index = if index > last { 2 * last - index } else { index };
+        signal[index.clamp(0, last) as usize]
+    };
+    let mut frame = vec![0.; fft_size];
+    let mut spectrum = vec![[0.; 2]; fft_size / 2 + 1];
+    let mut amplitude = vec![0.; fft_size / 2 + 1];
+    for (&t, out) in temporal_positions.iter().zip(mel_spectrogram.lines_mut()) {
+        let start = (t * fs as f64).round() as isize - (fft_size / 2) as isize;
+        for (n, (frame, window)) in frame.iter_mut().zip(&window).enumerate() {
+            *frame = reflect(start + n as isize) * window;
+        }
+        forward_real_fft.process(&frame, &mut spectrum)?;
+        for (amplitude, [re, im]) in amplitude.iter_mut().zip(&spectrum) {
+            *amplitude = re.hypot(*im);
+        }
+        filterbank.process(&amplitude, out)?;
+    }
+    Ok(mel_spectrogram)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filterbank() {
+        assert!((mel_to_hz(hz_to_mel(3000.)) - 3000.).abs() < 1e-9);
+        assert!((hz_to_mel(1000.) - 15.).abs() < 1e-12);
+
+        let filterbank = MelFilterbank::new(22050, 1024, &MelOption::default());
+        assert_eq!((filterbank.n_mels(), filterbank.frequency_axis_size()), (80, 513));
+        let peaks = (0..80)
+            .map(|mel| {
+                let weights = filterbank.weights(mel).unwrap();
+                assert!(weights.iter().all(|&weight| weight >= 0.));
+                (0..weights.len()).max_by(|&a, &b| weights[a].total_cmp(&weights[b])).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert!(peaks.windows(2).all(|peaks| peaks[0] <= peaks[1]));
+
+        // librosa.filters.mel(sr=2000, n_fft=8, n_mels=2)
+        let filterbank = MelFilterbank::new(2000, 8, &MelOption::new(2));
+        for (mel, expected) in [[0., 0.00225, 0.0015, 0., 0.], [0., 0., 0.0015, 0.00225, 0.]].iter().enumerate() {
+            for (expected, actual) in expected.iter().zip(filterbank.weights(mel).unwrap()) {
+                assert!((expected - actual).abs() < 1e-12, "{expected} != {actual}");
+            }
+        }
+
+        let option = MelOption {
+            fmax: Some(12000.),
+            ..MelOption::default()
+        };
+        assert_eq!(
+            MelFilterbank::try_new(22050, 1024, &option).err(),
+            Some(WorldError::InvalidParameter("fmin or fmax"))
+        );
+    }
+
+    #[test]
+    fn test_mel_spectrogram_from_signal() {
+        let fs = 16000;
+        let signal = (0..16000).map(|n| (2. * PI * 2000. * n as f64 / fs as f64).sin()).collect::<Vec<_>>();
+        let temporal_positions = (0..200).map(|i| i as f64 * 0.005).collect::<Vec<_>>();
+        let option = MelOption::new(40);
+        let mel_spectrogram = mel_spectrogram_from_signal(&signal, fs, &temporal_positions, 1024, &option).unwrap();
+        assert_eq!((mel_spectrogram.time_axis_size(), mel_spectrogram.frequency_axis_size()), (200, 40));
+
+        let filterbank = MelFilterbank::new(fs, 1024, &option);
+        let bin_2000 = 2000 * 1024 / fs as usize;
+        for line in mel_spectrogram.lines() {
+            let loudest = (0..40).max_by(|&a, &b| line[a].total_cmp(&line[b])).unwrap();
+            assert!(filterbank.weights(loudest).unwrap()[bin_2000] > 0.);
+        }
+
+        // the Hann window of 8 samples gives the amplitude 4 and 2 at the first two bins of DC
+        let mel_spectrogram = mel_spectrogram_from_signal(&[1.; 100], 2000, &[0.025], 8, &MelOption::new(2)).unwrap();
+        let line = mel_spectrogram.line(0).unwrap();
+        assert!((line[0] - (2. * 0.00225f64).ln()).abs() < 1e-9);
+        assert_eq!(line[1], DEFAULT_LOG_FLOOR.ln());
+
+        for t in [-0.001, 0.0501, 1e300] {
+            assert_eq!(
+                mel_spectrogram_from_signal(&[1.; 100], 2000, &[t], 8, &MelOption::new(2)).err(),
+                Some(WorldError::InvalidParameter("temporal_positions"))
+            );
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Objective metrics between the parameters or the signals of the reference and of the analysis, conversion or resynthesis.
+//!
+//! Frames with f0 of 0 are unvoiced, same as the analyses.
+
+use crate::codec::code_aperiodicity;
+use crate::error::WorldError;
+use crate::mel_cepstrum::{alpha_for_fs, spectrogram_to_mel_cepstrum};
+use crate::spectrogram_like::SpectrogramLike;
+
+/// Threshold of [`gross_pitch_error`] used in most literature, 20% of the reference f0.
+pub const DEFAULT_GROSS_PITCH_ERROR_THRESHOLD: f64 = 0.2;
+/// Range of the SNR of each segment in [`segmental_snr`] in dB.
+pub const SEGMENTAL_SNR_RANGE: (f64, f64) = (-10., 35.);
+
+fn validate_lengths(expected: usize, actual: usize) -> Result<(), WorldError> {
+    match (expected, actual) {
+        (0, _) => Err(WorldError::EmptySignal),
+        (expected, actual) if expected != actual => Err(WorldError::DifferentLength { expected, actual }),
+        _ => Ok(()),
+    }
+}
+
+fn validate_sizes(reference: &SpectrogramLike<f64>, other: &SpectrogramLike<f64>) -> Result<(), WorldError> {
+    validate_lengths(reference.time_axis_size(), other.time_axis_size())?;
+    validate_lengths(reference.frequency_axis_size(), other.frequency_axis_size())
+}
+
+/// Mel-cepstral distortion in dB between two frames of mel-cepstrum, excluding the 0th coefficient (the power).
+pub fn mel_cepstral_distance(reference: &[f64], other: &[f64]) -> f64 {
+    let squared = reference.iter().zip(other).skip(1).map(|(r, o)| (r - o).powi(2)).sum::<f64>();
+    10. / std::f64::consts::LN_10 * (2. * squared).sqrt()
+}
+
+/// Mean of [`mel_cepstral_distance`] between two mel-cepstra of the same order.
+pub fn mel_cepstral_distortion_of_mel_cepstra(reference: &SpectrogramLike<f64>, other: &SpectrogramLike<f64>) -> Result<f64, WorldError> {
+    validate_sizes(reference, other)?;
+    let total = reference
+        .lines()
+        .zip(other.lines())
+        .map(|(reference, other)| mel_cepstral_distance(reference, other))
+        .sum::<f64>();
+    Ok(total / reference.time_axis_size() as f64)
+}
+
+/// Mean mel-cepstral distortion in dB between two spectral envelopes of the same size,
+/// converted into the mel-cepstrum of `order` with the alpha of [`alpha_for_fs`].
+pub fn mel_cepstral_distortion(reference: &SpectrogramLike<f64>, other: &SpectrogramLike<f64>, fs: u32, order: usize) -> Result<f64, WorldError> {
+    validate_sizes(reference, other)?;
+    let alpha = alpha_for_fs(fs);
+    let reference = spectrogram_to_mel_cepstrum(reference, order, alpha)?;
+    let other = spectrogram_to_mel_cepstrum(other, order, alpha)?;
+    mel_cepstral_distortion_of_mel_cepstra(&reference, &other)
+}
+
+/// Ratios of f0 of the frames voiced in both.
+fn voiced_ratios<'a>(reference: &'a [f64], other: &'a [f64]) -> impl Iterator<Item = f64> + 'a {
+    reference.iter().zip(other).filter(|&(&r, &o)| r > 0. && o > 0.).map(|(r, o)| o / r)
+}
+
+/// Root mean square error in cents over the frames voiced in both, or `None` if there are no such frames.
+pub fn f0_rmse_cents(reference: &[f64], other: &[f64]) -> Result<Option<f64>, WorldError> {
+    validate_lengths(reference.len(), other.len())?;
+    let (count, total) = voiced_ratios(reference, other).fold((0, 0.), |(count, total), ratio| (count + 1, total + (1200. * ratio.log2()).powi(2)));
+    Ok((count > 0).then(|| (total / count as f64).sqrt()))
+}
+
+/// Rate of the frames whose f0 differs by more than `threshold` (e.g. [`DEFAULT_GROSS_PITCH_ERROR_THRESHOLD`]) of the reference
+/// among the frames voiced in both, or `None` if there are no such frames.
+pub fn gross_pitch_error(reference: &[f64], other: &[f64], threshold: f64) -> Result<Option<f64>, WorldError> {
+    validate_lengths(reference.len(), other.len())?;
+    let (count, errors) = voiced_ratios(reference, other).fold((0, 0), |(count, errors), ratio| {
+        (count + 1, errors + usize::from((ratio - 1.).abs() > threshold))
+    });
+    Ok((count > 0).then(|| errors as f64 / count as f64))
+}
+
+/// Rate of the frames which are voiced in one and unvoiced in the other.
+pub fn voicing_decision_error(reference: &[f64], other: &[f64]) -> Result<f64, WorldError> {
+    validate_lengths(reference.len(), other.len())?;
+    let errors = reference.iter().zip(other).filter(|&(&r, &o)| (r > 0.) != (o > 0.)).count();
+    Ok(errors as f64 / reference.len() as f64)
+}
+
+/// Root mean square error in dB of the band aperiodicity coded by [`code_aperiodicity`], over all frames and bands.
+pub fn band_aperiodicity_distortion(reference: &SpectrogramLike<f64>, other: &SpectrogramLike<f64>, fs: u32) -> Result<f64, WorldError> {
+    validate_sizes(reference, other)?;
+    let reference = code_aperiodicity(reference, fs)?;
+    let other = code_aperiodicity(other, fs)?;
+    let (count, total) = reference
+        .lines()
+        .flatten()
+        .zip(other.lines().flatten())
+        .fold((0, 0.), |(count, total), (r, o)| (count + 1, total + (r - o).powi(2)));
+    Ok((total / count as f64).sqrt())
+}
+
+fn snr_of(reference: &[f64], other: &[f64]) -> f64 {
+    let signal = reference.iter().map(|r| r * r).sum::<f64>();
+    let noise = reference.iter().zip(other).map(|(r, o)| (r - o).powi(2)).sum::<f64>();
+    10. * (signal / noise).log10()
+}
+
+/// Signal-to-noise ratio in dB of `other` against `reference` of the same length, which is infinite if they are identical.
+pub fn snr(reference: &[f64], other: &[f64]) -> Result<f64, WorldError> {
+    validate_lengths(reference.len(), other.len())?;
+    Ok(snr_of(reference, other))
+}
+
+/// Mean of the SNR of the segments of `segment_length` samples, each clamped into [`SEGMENTAL_SNR_RANGE`].
+///
+/// Segments which are silent in both are skipped, and `None` is returned if all segments are skipped.
+pub fn segmental_snr(reference: &[f64], other: &[f64], segment_length: usize) -> Result<Option<f64>, WorldError> {
+    validate_lengths(reference.len(), other.len())?;
+    if segment_length == 0 {
+        return Err(WorldError::InvalidParameter("segment_length"));
+    }
+    let (min, max) = SEGMENTAL_SNR_RANGE;
+    let (count, total) = reference
+        .chunks(segment_length)
+        .zip(other.chunks(segment_length))
+        .map(|(reference, other)| snr_of(reference, other))
+        .filter(|snr| !snr.is_nan())
+        .fold((0, 0.), |(count, total), snr| (count + 1, total + snr.clamp(min, max)));
+    Ok((count > 0).then(|| total / count as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_f0_metrics() {
+        let reference = [0., 100., 100., 200., 200.];
+        let other = [0., 200., 100., 0., 220.];
+        // an octave error and 10% error
+        let expected = ((1200f64.powi(2) + 0. + (1200. * 1.1f64.log2()).powi(2)) / 3.).sqrt();
+        assert!((f0_rmse_cents(&reference, &other).unwrap().unwrap() - expected).abs() < 1e-9);
+        assert_eq!(
+            gross_pitch_error(&reference, &other, DEFAULT_GROSS_PITCH_ERROR_THRESHOLD),
+            Ok(Some(1. / 3.))
+        );
+        assert_eq!(voicing_decision_error(&reference, &other), Ok(0.2));
+        assert_eq!(f0_rmse_cents(&[0., 100.], &[100., 0.]), Ok(None));
+        assert_eq!(
+            f0_rmse_cents(&reference, &other[1..]),
+            Err(WorldError::DifferentLength { expected: 5, actual: 4 })
+        );
+    }
+
+    #[test]
+    fn test_snr() {
+        let reference = [1., -1., 1., -1., 0., 0., 0., 0.];
+        let other = [1.1, -0.9, 1.1, -0.9, 0., 0., 0., 0.];
+        assert!((snr(&reference, &other).unwrap() - 20.).abs() < 1e-9);
+        assert_eq!(snr(&reference, &reference), Ok(f64::INFINITY));
+        // the silent segment is skipped
+        assert!((segmental_snr(&reference, &other, 4).unwrap().unwrap() - 20.).abs() < 1e-9);
+        assert_eq!(segmental_snr(&reference, &reference, 3), Ok(Some(35.)));
+        assert_eq!(segmental_snr(&[0.; 4], &[0.; 4], 2), Ok(None));
+    }
+
+    #[test]
+    fn test_spectral_distortions() {
+        let power = |_, bin| 1. + (bin as f64 / 50.).sin().powi(2);
+        let reference = SpectrogramLike::from_fn(2, 513, power);
+        // twice the power, which changes only the 0th coefficient
+        let other = SpectrogramLike::from_fn(2, 513, |frame, bin| 2. * power(frame, bin));
+        assert!(mel_cepstral_distortion(&reference, &reference, 48000, 24).unwrap().abs() < 1e-9);
+        assert!(mel_cepstral_distortion(&reference, &other, 48000, 24).unwrap().abs() < 1e-6);
+
+        let aperiodicity = SpectrogramLike::from_fn(2, 513, |_, _| 0.1);
+        let other = SpectrogramLike::from_fn(2, 513, |_, _| 0.01);
+        assert!((band_aperiodicity_distortion(&aperiodicity, &other, 48000).unwrap() - 20.).abs() < 1e-6);
+    }
+}
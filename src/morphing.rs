@@ -0,0 +1,272 @@
+//! Morphing between two analyses, aligned frame by frame or by a [`WarpingPath`] of [`crate::dtw`].
+//!
+//! Each parameter has its own morph ratio, where 0 gives the first analysis and 1 gives the second,
+//! and the ratio may change frame by frame. Ratios outside \[0, 1\] extrapolate, except for the aperiodicity.
+
+use crate::dtw::WarpingPath;
+use crate::error::WorldError;
+use crate::signal_analyzer::AnalyzeResult;
+use crate::spectrogram_like::SpectrogramLike;
+use crate::synthesis::SynthesisParameters;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MorphRatio {
+    Constant(f64),
+    /// Ratio of each frame.
+    PerFrame(Vec<f64>),
+}
+
+impl MorphRatio {
+    fn validate(&self, frames: usize) -> Result<(), WorldError> {
+        let ratios = match self {
+            MorphRatio::Constant(ratio) => std::slice::from_ref(ratio),
+            MorphRatio::PerFrame(ratios) if ratios.len() != frames => {
+                return Err(WorldError::DifferentLength {
+                    expected: frames,
+                    actual: ratios.len(),
+                })
+            }
+            MorphRatio::PerFrame(ratios) => ratios,
+        };
+        if ratios.iter().all(|ratio| ratio.is_finite()) {
+            Ok(())
+        } else {
+            Err(WorldError::InvalidParameter("morph ratio"))
+        }
+    }
+
+    fn at(&self, frame: usize) -> f64 {
+        match self {
+            MorphRatio::Constant(ratio) => *ratio,
+            MorphRatio::PerFrame(ratios) => ratios[frame],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MorphOption {
+    /// Ratio of f0, interpolated in the log domain.
+    pub f0: MorphRatio,
+    /// Ratio of the spectral envelope, interpolated in the log domain.
+    pub spectrogram: MorphRatio,
+    /// Ratio of the aperiodicity, interpolated linearly.
+    pub aperiodicity: MorphRatio,
+    /// Ratio of the frequencies of the formants of the second to the first, e.g. 1.2 from a male to a female voice.
+    ///
+    /// If given, the envelopes are stretched along the frequency axis so that their formants meet at the morphed frequencies,
+    /// instead of cross-fading the formants at fixed frequencies.
+    pub frequency_ratio: Option<f64>,
+}
+
+impl MorphOption {
+    /// The same constant `ratio` for all parameters without the frequency alignment.
+    pub fn new(ratio: f64) -> MorphOption {
+        MorphOption {
+            f0: MorphRatio::Constant(ratio),
+            spectrogram: MorphRatio::Constant(ratio),
+            aperiodicity: MorphRatio::Constant(ratio),
+            frequency_ratio: None,
+        }
+    }
+}
+
+/// Frames voiced in both take the geometric interpolation of f0,
+/// and frames voiced in only one take its f0 if the ratio is closer to it, or are unvoiced otherwise.
+fn morph_f0(a: f64, b: f64, ratio: f64) -> f64 {
+    match (a > 0., b > 0.) {
+        (true, true) => (a.ln() * (1. - ratio) + b.ln() * ratio).exp(),
+        (true, false) if ratio < 0.5 => a,
+        (false, true) if ratio >= 0.5 => b,
+        _ => 0.,
+    }
+}
+
+/// Log of `line` at the fractional `bin`, holding the values beyond both ends.
+///
+/// The power is floored at the smallest positive value, so that zero power never gives NaN in the mix.
+fn log_at(line: &[f64], bin: f64) -> f64 {
+    let log = |power: f64| power.max(f64::MIN_POSITIVE).ln();
+    let last = line.len() - 1;
+    let bin = bin.clamp(0., last as f64);
+    let index = (bin as usize).min(last.saturating_sub(1));
+    let t = bin - index as f64;
+    match line.get(index + 1) {
+        Some(&next) => log(line[index]) * (1. - t) + log(next) * t,
+        None => log(line[index]),
+    }
+}
+
+fn validate_sizes(a: &SynthesisParameters, b: &SynthesisParameters) -> Result<(), WorldError> {
+    if a.fs != b.fs {
+        return Err(WorldError::InvalidSampleRate(b.fs));
+    }
+    if a.frame_period != b.frame_period {
+        return Err(WorldError::InvalidFramePeriod(b.frame_period));
+    }
+    for (expected, actual) in [
+        (a.f0.len(), b.f0.len()),
+        (a.f0.len(), a.spectrogram.time_axis_size()),
+        (a.f0.len(), a.aperiodicity.time_axis_size()),
+        (b.f0.len(), b.spectrogram.time_axis_size()),
+        (b.f0.len(), b.aperiodicity.time_axis_size()),
+        (a.spectrogram.frequency_axis_size(), b.spectrogram.frequency_axis_size()),
+        (a.aperiodicity.frequency_axis_size(), b.aperiodicity.frequency_axis_size()),
+    ] {
+        if expected != actual {
+            return Err(WorldError::DifferentLength { expected, actual });
+        }
+    }
+    Ok(())
+}
+
+/// Morph the parameters of the same fs, frame period and frequency axes.
+///
+/// `path` aligns the frames of `b` as the source to the frames of `a` as the reference, e.g. by [`crate::dtw::dtw_spectrograms`],
+/// and the result has the frames of `a`. Without `path`, both must have the same number of frames.
+pub fn morph_parameters(
+    a: &SynthesisParameters,
+    b: &SynthesisParameters,
+    path: Option<&WarpingPath>,
+    option: &MorphOption,
+) -> Result<SynthesisParameters, WorldError> {
+    let warped;
+    let b = match path {
+        Some(path) => {
+            warped = path.apply(b)?;
+            &warped
+        }
+        None => b,
+    };
+    validate_sizes(a, b)?;
+    let frames = a.f0.len();
+    for ratio in [&option.f0, &option.spectrogram, &option.aperiodicity] {
+        ratio.validate(frames)?;
+    }
+    if option.frequency_ratio.is_some_and(|ratio| !(ratio.is_finite() && ratio > 0.)) {
+        return Err(WorldError::InvalidParameter("frequency_ratio"));
+    }
+
+    let f0 = (0..frames).map(|frame| morph_f0(a.f0[frame], b.f0[frame], option.f0.at(frame))).collect();
+
+    let mut spectrogram = SpectrogramLike::try_new(frames, a.spectrogram.frequency_axis_size())?;
+    let lines = a.spectrogram.lines().zip(b.spectrogram.lines());
+    for (frame, (out, (a, b))) in spectrogram.lines_mut().zip(lines).enumerate() {
+        let ratio = option.spectrogram.at(frame);
+        // a formant at f of the first and at f * frequency_ratio of the second moves to f * frequency_ratio^ratio
+        let frequency_ratio = option.frequency_ratio.unwrap_or(1.);
+        let (scale_a, scale_b) = (frequency_ratio.powf(-ratio), frequency_ratio.powf(1. - ratio));
+        for (bin, out) in out.iter_mut().enumerate() {
+            let bin = bin as f64;
+            *out = (log_at(a, bin * scale_a) * (1. - ratio) + log_at(b, bin * scale_b) * ratio).exp();
+        }
+    }
+
+    let mut aperiodicity = SpectrogramLike::try_new(frames, a.aperiodicity.frequency_axis_size())?;
+    let lines = a.aperiodicity.lines().zip(b.aperiodicity.lines());
+    for (frame, (out, (a, b))) in aperiodicity.lines_mut().zip(lines).enumerate() {
+        let ratio = option.aperiodicity.at(frame);
+        for (out, (a, b)) in out.iter_mut().zip(a.iter().zip(b)) {
+            *out = (a * (1. - ratio) + b * ratio).clamp(0., 1.);
+        }
+    }
+
+    Ok(SynthesisParameters {
+        fs: a.fs,
+        frame_period: a.frame_period,
+        f0,
+        spectrogram,
+        aperiodicity,
+    })
+}
+
+/// Morph two analyses in which f0, the spectral envelope and the aperiodicity are calculated. See [`morph_parameters`].
+pub fn morph(a: &AnalyzeResult, b: &AnalyzeResult, path: Option<&WarpingPath>, option: &MorphOption) -> Result<SynthesisParameters, WorldError> {
+    morph_parameters(&SynthesisParameters::from_result(a)?, &SynthesisParameters::from_result(b)?, path, option)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters(f0: &[f64], power: impl Fn(usize) -> f64, aperiodicity: f64) -> SynthesisParameters {
+        SynthesisParameters {
+            fs: 16000,
+            frame_period: 5.,
+            f0: f0.to_vec(),
+            spectrogram: SpectrogramLike::from_fn(f0.len(), 65, |_, bin| power(bin)),
+            aperiodicity: SpectrogramLike::from_fn(f0.len(), 65, |_, _| aperiodicity),
+        }
+    }
+
+    #[test]
+    fn test_morph() {
+        let a = parameters(&[100., 100., 0., 0.], |_| 1., 0.1);
+        let b = parameters(&[400., 0., 200., 0.], |_| 100., 0.5);
+        let morphed = morph_parameters(&a, &b, None, &MorphOption::new(0.5)).unwrap();
+        assert!((morphed.f0[0] - 200.).abs() < 1e-9);
+        assert_eq!(morphed.f0[1..], [0., 200., 0.]);
+        assert!(morphed.spectrogram.lines().flatten().all(|value| (value - 10.).abs() < 1e-9));
+        assert!(morphed.aperiodicity.lines().flatten().all(|value| (value - 0.3).abs() < 1e-9));
+
+        let option = MorphOption {
+            f0: MorphRatio::PerFrame(vec![0., 1., 0., 1.]),
+            ..MorphOption::new(1.)
+        };
+        let morphed = morph_parameters(&a, &b, None, &option).unwrap();
+        assert!((morphed.f0[0] - 100.).abs() < 1e-9);
+        assert_eq!(morphed.f0[1..], [0., 0., 0.]);
+        assert!(morphed.spectrogram.lines().flatten().all(|value| (value - 100.).abs() < 1e-9));
+
+        let option = MorphOption {
+            f0: MorphRatio::PerFrame(vec![0.; 3]),
+            ..MorphOption::new(0.5)
+        };
+        assert_eq!(
+            morph_parameters(&a, &b, None, &option).err(),
+            Some(WorldError::DifferentLength { expected: 4, actual: 3 })
+        );
+    }
+
+    #[test]
+    fn test_frequency_alignment() {
+        // peaks at bin 16 and 32 meet at bin 16 * 2^0.5
+        let peak = |center: f64| move |bin: usize| 1. + (-((bin as f64 - center) / 2.).powi(2)).exp() * 100.;
+        let a = parameters(&[100.], peak(16.), 0.1);
+        let b = parameters(&[100.], peak(32.), 0.1);
+        let option = MorphOption {
+            frequency_ratio: Some(2.),
+            ..MorphOption::new(0.5)
+        };
+        let morphed = morph_parameters(&a, &b, None, &option).unwrap();
+        let line = morphed.spectrogram.line(0).unwrap();
+        let peak_bin = (0..line.len()).max_by(|&i, &j| line[i].total_cmp(&line[j])).unwrap();
+        assert_eq!(peak_bin, (16. * 2f64.sqrt()).round() as usize);
+    }
+
+    #[test]
+    fn test_morph_zero_power() {
+        let a = parameters(&[100.], |bin| if bin == 0 { 0. } else { 1. }, 0.1);
+        let b = parameters(&[100.], |_| 1., 0.1);
+        for ratio in [0., 0.5, 1.] {
+            let morphed = morph_parameters(&a, &b, None, &MorphOption::new(ratio)).unwrap();
+            assert!(morphed.spectrogram.lines().flatten().all(|value| value.is_finite()), "{ratio}");
+        }
+    }
+
+    #[test]
+    fn test_morph_aligned() {
+        let a = parameters(&[100.; 4], |_| 1., 0.1);
+        let b = parameters(&[0., 100., 0., 0., 100., 0.], |_| 100., 0.5);
+        assert_eq!(
+            morph_parameters(&a, &b, None, &MorphOption::new(1.)).err(),
+            Some(WorldError::DifferentLength { expected: 4, actual: 6 })
+        );
+        let path = WarpingPath {
+            pairs: vec![(0, 0), (1, 1), (1, 2), (2, 3), (3, 4), (3, 5)],
+            cost: 0.,
+        };
+        let morphed = morph_parameters(&a, &b, Some(&path), &MorphOption::new(1.)).unwrap();
+        assert_eq!(morphed.f0.iter().map(|&f0| f0 > 0.).collect::<Vec<_>>(), [false, true, false, true]);
+        assert_eq!(morphed.spectrogram.time_axis_size(), 4);
+    }
+}
@@ -0,0 +1,560 @@
+//! Reading and writing of NumPy `.npy` and `.npz` files, enabled by the `npy` feature.
+//!
+//! Arrays have the same layout as pyworld gives, i.e. f0 and temporal positions are float64 arrays of shape `(frames,)`,
+//! and the spectrogram and the aperiodicity are float64 arrays of shape `(frames, fft_size / 2 + 1)` in C order.
+//!
+//! format: <https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html>
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+
+use zip::result::ZipError;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::error::{validate_fs, WorldError};
+use crate::signal_analyzer::AnalyzeResult;
+use crate::spectrogram_like::SpectrogramLike;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+/// Total length of the magic, the version, the header length and the header is a multiple of this.
+const HEADER_ALIGNMENT: usize = 64;
+
+/// Names of the arrays in `.npz` files written by [`AnalyzeResult::write_npz`].
+pub mod keys {
+    /// Analyzed signal, float64 of shape `(samples,)`.
+    pub const SIGNAL: &str = "x";
+    /// Analyzed range of the signal, int64 of shape `(2,)`.
+    pub const RANGE: &str = "range";
+    /// int64 of shape `()`.
+    pub const FS: &str = "fs";
+    /// float64 of shape `()` in milliseconds.
+    pub const FRAME_PERIOD: &str = "frame_period";
+    pub const F0: &str = "f0";
+    pub const TEMPORAL_POSITIONS: &str = "t";
+    pub const SPECTROGRAM: &str = "sp";
+    pub const APERIODICITY: &str = "ap";
+}
+
+#[derive(Debug)]
+pub enum NpyError {
+    Io(io::Error),
+    Zip(ZipError),
+    /// The file is not a `.npy` file, or its header can not be parsed.
+    InvalidHeader,
+    /// Data type other than little endian float64, float32, int64 and int32.
+    UnsupportedDataType(String),
+    /// Shape of the array is not the expected one.
+    InvalidShape(Vec<usize>),
+    /// Required array is missing in the `.npz` file.
+    MissingArray(&'static str),
+    World(WorldError),
+}
+
+impl Display for NpyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NpyError::Io(error) => write!(f, "{error}"),
+            NpyError::Zip(error) => write!(f, "{error}"),
+            NpyError::InvalidHeader => write!(f, "invalid npy header"),
+            NpyError::UnsupportedDataType(descr) => write!(f, "unsupported data type {descr}"),
+            NpyError::InvalidShape(shape) => write!(f, "invalid shape {shape:?}"),
+            NpyError::MissingArray(name) => write!(f, "missing array {name}"),
+            NpyError::World(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for NpyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NpyError::Io(error) => Some(error),
+            NpyError::Zip(error) => Some(error),
+            NpyError::World(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NpyError {
+    fn from(value: io::Error) -> Self {
+        NpyError::Io(value)
+    }
+}
+
+impl From<ZipError> for NpyError {
+    fn from(value: ZipError) -> Self {
+        NpyError::Zip(value)
+    }
+}
+
+impl From<WorldError> for NpyError {
+    fn from(value: WorldError) -> Self {
+        NpyError::World(value)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum DataType {
+    F8,
+    F4,
+    I8,
+    I4,
+}
+
+impl DataType {
+    fn parse(descr: &str) -> Result<DataType, NpyError> {
+        match descr {
+            "<f8" => Ok(DataType::F8),
+            "<f4" => Ok(DataType::F4),
+            "<i8" => Ok(DataType::I8),
+            "<i4" => Ok(DataType::I4),
+            _ => Err(NpyError::UnsupportedDataType(descr.to_owned())),
+        }
+    }
+
+    fn descr(self) -> &'static str {
+        match self {
+            DataType::F8 => "<f8",
+            DataType::F4 => "<f4",
+            DataType::I8 => "<i8",
+            DataType::I4 => "<i4",
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            DataType::F8 | DataType::I8 => 8,
+            DataType::F4 | DataType::I4 => 4,
+        }
+    }
+
+    fn to_f64(self, bytes: &[u8]) -> f64 {
+        match self {
+            DataType::F8 => f64::from_le_bytes(bytes.try_into().unwrap()),
+            DataType::F4 => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            DataType::I8 => i64::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            DataType::I4 => i32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        }
+    }
+}
+
+fn write_header(mut writer: impl Write, data_type: DataType, shape: &[usize]) -> io::Result<()> {
+    let shape = match shape {
+        [len] => format!("({len},)"),
+        shape => format!("({})", shape.iter().map(usize::to_string).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {shape}, }}", data_type.descr());
+    // version 1.0 has 10 bytes before the header, which ends with '\n'
+    let padding = (HEADER_ALIGNMENT - (10 + header.len() + 1) % HEADER_ALIGNMENT) % HEADER_ALIGNMENT;
+    header.extend(std::iter::repeat_n(' ', padding));
+    header.push('\n');
+    let header_len = u16::try_from(header.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too long npy header"))?;
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&header_len.to_le_bytes())?;
+    writer.write_all(header.as_bytes())
+}
+
+/// Write `data` as a float64 array of `shape` in C order.
+///
+/// # Panics
+/// Panics if the product of `shape` is not the length of `data`.
+pub fn write_f64(mut writer: impl Write, shape: &[usize], data: &[f64]) -> io::Result<()> {
+    assert_eq!(shape.iter().product::<usize>(), data.len());
+    let mut writer = BufWriter::new(&mut writer);
+    write_header(&mut writer, DataType::F8, shape)?;
+    for value in data {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    writer.flush()
+}
+
+fn write_i64(mut writer: impl Write, shape: &[usize], data: &[i64]) -> io::Result<()> {
+    write_header(&mut writer, DataType::I8, shape)?;
+    for value in data {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Value of `key` in the header, which is a Python dict literal.
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str, NpyError> {
+    let start = header.find(&format!("'{key}'")).ok_or(NpyError::InvalidHeader)? + key.len() + 2;
+    let value = header[start..]
+        .trim_start()
+        .strip_prefix(':')
+        .ok_or(NpyError::InvalidHeader)?
+        .trim_start();
+    let end = if value.starts_with('(') {
+        value.find(')').ok_or(NpyError::InvalidHeader)? + 1
+    } else {
+        value.find([',', '}']).ok_or(NpyError::InvalidHeader)?
+    };
+    Ok(value[..end].trim())
+}
+
+/// Read an array of float64, float32, int64 or int32 as float64, and return its shape and data in C order.
+pub fn read_f64(reader: impl Read) -> Result<(Vec<usize>, Vec<f64>), NpyError> {
+    let mut reader = BufReader::new(reader);
+    let mut preamble = [0; 8];
+    reader.read_exact(&mut preamble)?;
+    if &preamble[..6] != MAGIC {
+        return Err(NpyError::InvalidHeader);
+    }
+    let header_len = match preamble[6] {
+        1 => {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0; 4];
+            reader.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        _ => return Err(NpyError::InvalidHeader),
+    };
+    let mut header = vec![0; header_len];
+    reader.read_exact(&mut header)?;
+    let header = String::from_utf8(header).map_err(|_| NpyError::InvalidHeader)?;
+
+    let descr = header_value(&header, "descr")?.trim_matches(|c| c == '\'' || c == '"');
+    let data_type = DataType::parse(descr)?;
+    let fortran_order = match header_value(&header, "fortran_order")? {
+        "True" => true,
+        "False" => false,
+        _ => return Err(NpyError::InvalidHeader),
+    };
+    let shape = header_value(&header, "shape")?
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(str::trim)
+        .filter(|len| !len.is_empty())
+        .map(|len| len.parse().map_err(|_| NpyError::InvalidHeader))
+        .collect::<Result<Vec<usize>, _>>()?;
+
+    let len = shape
+        .iter()
+        .try_fold(1usize, |len, &dim| len.checked_mul(dim))
+        .ok_or(NpyError::InvalidHeader)?;
+    let size = len.checked_mul(data_type.size()).ok_or(NpyError::InvalidHeader)?;
+    let mut bytes = Vec::new();
+    reader.take(size as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != size {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    let mut data = bytes
+        .chunks_exact(data_type.size())
+        .map(|bytes| data_type.to_f64(bytes))
+        .collect::<Vec<_>>();
+    if fortran_order && shape.len() == 2 {
+        let (rows, columns) = (shape[0], shape[1]);
+        data = (0..rows * columns).map(|i| data[i % columns * rows + i / columns]).collect();
+    } else if fortran_order && shape.len() > 2 {
+        return Err(NpyError::InvalidShape(shape));
+    }
+    Ok((shape, data))
+}
+
+/// Shape and data in C order.
+type Array = (Vec<usize>, Vec<f64>);
+
+fn into_1d((shape, data): Array) -> Result<Box<[f64]>, NpyError> {
+    match shape.len() {
+        1 => Ok(data.into_boxed_slice()),
+        _ => Err(NpyError::InvalidShape(shape)),
+    }
+}
+
+fn into_scalar((shape, data): Array) -> Result<f64, NpyError> {
+    if shape.is_empty() || shape == [1] {
+        Ok(data[0])
+    } else {
+        Err(NpyError::InvalidShape(shape))
+    }
+}
+
+fn into_spectrogram((shape, data): Array) -> Result<SpectrogramLike<f64>, NpyError> {
+    let &[time_axis_size, frequency_axis_size] = shape.as_slice() else {
+        return Err(NpyError::InvalidShape(shape));
+    };
+    let mut spectrogram = SpectrogramLike::try_new(time_axis_size, frequency_axis_size)?;
+    for (line, data) in spectrogram.lines_mut().zip(data.chunks_exact(frequency_axis_size)) {
+        line.copy_from_slice(data);
+    }
+    Ok(spectrogram)
+}
+
+/// Read `{name}.npy` in `zip`, or `None` if it does not exist.
+fn read_entry(zip: &mut ZipArchive<impl Read + Seek>, name: &str) -> Result<Option<Array>, NpyError> {
+    match zip.by_name(&format!("{name}.npy")) {
+        Ok(file) => read_f64(file).map(Some),
+        Err(ZipError::FileNotFound) => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+impl SpectrogramLike<f64> {
+    /// Write as a float64 array of shape `(time_axis_size, frequency_axis_size)`.
+    pub fn write_npy(&self, writer: impl Write) -> io::Result<()> {
+        let data = self.lines().flatten().copied().collect::<Vec<_>>();
+        write_f64(writer, &[self.time_axis_size(), self.frequency_axis_size()], &data)
+    }
+
+    /// Read a 2-dimensional array, such as `sp` and `ap` of pyworld.
+    pub fn read_npy(reader: impl Read) -> Result<SpectrogramLike<f64>, NpyError> {
+        into_spectrogram(read_f64(reader)?)
+    }
+}
+
+impl AnalyzeResult {
+    /// Write all arrays into a `.npz` file of [`keys`], like `numpy.savez`.
+    ///
+    /// The arrays which are not calculated are omitted.
+    pub fn write_npz(&self, writer: impl Write + Seek) -> Result<(), NpyError> {
+        let mut zip = ZipWriter::new(writer);
+        let options = FileOptions::default().compression_method(CompressionMethod::Stored).large_file(true);
+        let file_name = |name: &str| format!("{name}.npy");
+
+        zip.start_file(file_name(keys::SIGNAL), options)?;
+        write_f64(&mut zip, &[self.signal.len()], &self.signal)?;
+        zip.start_file(file_name(keys::RANGE), options)?;
+        write_i64(&mut zip, &[2], &[self.range.start as i64, self.range.end as i64])?;
+        zip.start_file(file_name(keys::FS), options)?;
+        write_i64(&mut zip, &[], &[self.fs as i64])?;
+        zip.start_file(file_name(keys::FRAME_PERIOD), options)?;
+        write_f64(&mut zip, &[], &[self.frame_period])?;
+        if let Some(f0) = &self.f0 {
+            zip.start_file(file_name(keys::F0), options)?;
+            write_f64(&mut zip, &[f0.len()], f0)?;
+        }
+        if let Some(temporal_positions) = &self.temporal_positions {
+            zip.start_file(file_name(keys::TEMPORAL_POSITIONS), options)?;
+            write_f64(&mut zip, &[temporal_positions.len()], temporal_positions)?;
+        }
+        if let Some(spectrogram) = &self.spectrogram {
+            zip.start_file(file_name(keys::SPECTROGRAM), options)?;
+            spectrogram.write_npy(&mut zip)?;
+        }
+        if let Some(aperiodicity) = &self.aperiodicity {
+            zip.start_file(file_name(keys::APERIODICITY), options)?;
+            aperiodicity.write_npy(&mut zip)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    /// Read a `.npz` file of [`keys`], where only fs and frame_period are required.
+    ///
+    /// Without the signal, the signal is empty and the range is `0..0`.
+    /// fs must be an integer, and f0, the temporal positions, the spectrogram and the aperiodicity must have the same number of frames.
+    pub fn read_npz(reader: impl Read + Seek) -> Result<AnalyzeResult, NpyError> {
+        let mut zip = ZipArchive::new(reader)?;
+        let signal = read_entry(&mut zip, keys::SIGNAL)?.map(into_1d).transpose()?.unwrap_or_default();
+        let range = match read_entry(&mut zip, keys::RANGE)? {
+            Some((shape, data)) if shape == [2] => {
+                let in_signal = |index: f64| index.is_finite() && (0. ..=signal.len() as f64).contains(&index);
+                if !(in_signal(data[0]) && in_signal(data[1]) && data[0] <= data[1]) {
+                    return Err(WorldError::InvalidParameter("range").into());
+                }
+                data[0] as usize..data[1] as usize
+            }
+            Some((shape, _)) => return Err(NpyError::InvalidShape(shape)),
+            None => 0..signal.len(),
+        };
+        let fs = match into_scalar(read_entry(&mut zip, keys::FS)?.ok_or(NpyError::MissingArray(keys::FS))?)? {
+            fs if fs.fract() == 0. && (0. ..=u32::MAX as f64).contains(&fs) => validate_fs(fs as u32)?,
+            fs => return Err(WorldError::InvalidSampleRate(fs as u32).into()),
+        };
+        let frame_period = into_scalar(read_entry(&mut zip, keys::FRAME_PERIOD)?.ok_or(NpyError::MissingArray(keys::FRAME_PERIOD))?)?;
+        let f0 = read_entry(&mut zip, keys::F0)?.map(into_1d).transpose()?;
+        let temporal_positions = read_entry(&mut zip, keys::TEMPORAL_POSITIONS)?.map(into_1d).transpose()?;
+        let spectrogram = read_entry(&mut zip, keys::SPECTROGRAM)?.map(into_spectrogram).transpose()?;
+        let aperiodicity = read_entry(&mut zip, keys::APERIODICITY)?.map(into_spectrogram).transpose()?;
+        let mut frames = [
+            f0.as_ref().map(|f0| f0.len()),
+            temporal_positions.as_ref().map(|temporal_positions| temporal_positions.len()),
+            spectrogram.as_ref().map(SpectrogramLike::time_axis_size),
+            aperiodicity.as_ref().map(SpectrogramLike::time_axis_size),
+        ]
+        .into_iter()
+        .flatten();
+        if let Some(expected) = frames.next() {
+            if let Some(actual) = frames.find(|&actual| actual != expected) {
+                return Err(WorldError::DifferentLength { expected, actual }.into());
+            }
+        }
+        Ok(AnalyzeResult {
+            signal,
+            range,
+            fs,
+            frame_period,
+            f0,
+            temporal_positions,
+            spectrogram,
+            aperiodicity,
+        })
+    }
+
+    pub fn save_npz(&self, path: impl AsRef<Path>) -> Result<(), NpyError> {
+        self.write_npz(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load_npz(path: impl AsRef<Path>) -> Result<AnalyzeResult, NpyError> {
+        AnalyzeResult::read_npz(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn spectrogram(time_axis_size: usize, frequency_axis_size: usize, offset: f64) -> SpectrogramLike<f64> {
+        SpectrogramLike::from_fn(time_axis_size, frequency_axis_size, |frame, bin| {
+            (frame * frequency_axis_size + bin) as f64 + offset
+        })
+    }
+
+    #[test]
+    fn test_npy() {
+        let mut bytes = Vec::new();
+        write_f64(&mut bytes, &[2], &[1., 2.]).unwrap();
+        assert_eq!(bytes.len(), 128 + 16);
+        assert_eq!(&bytes[..10], b"\x93NUMPY\x01\x00\x76\x00");
+        assert!(bytes[10..128].starts_with(b"{'descr': '<f8', 'fortran_order': False, 'shape': (2,), }"));
+        assert_eq!(bytes[127], b'\n');
+        assert_eq!(read_f64(bytes.as_slice()).unwrap(), (vec![2], vec![1., 2.]));
+
+        let sp = spectrogram(3, 5, 0.);
+        let mut bytes = Vec::new();
+        sp.write_npy(&mut bytes).unwrap();
+        assert_eq!(bytes.len() % HEADER_ALIGNMENT, (3 * 5 * 8) % HEADER_ALIGNMENT);
+        let read = SpectrogramLike::read_npy(bytes.as_slice()).unwrap();
+        assert_eq!(read.lines().collect::<Vec<_>>(), sp.lines().collect::<Vec<_>>());
+        assert!(matches!(SpectrogramLike::read_npy(&b"not npy"[..]), Err(NpyError::Io(_))));
+        assert!(matches!(
+            SpectrogramLike::read_npy(&b"\x93NUMPX\x01\x00"[..]),
+            Err(NpyError::InvalidHeader)
+        ));
+
+        let header = "{'descr': '<f8', 'fortran_order': False, 'shape': (4611686018427387904,), }";
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend((header.len() as u16).to_le_bytes());
+        bytes.extend(header.as_bytes());
+        assert!(matches!(read_f64(bytes.as_slice()), Err(NpyError::InvalidHeader)));
+    }
+
+    #[test]
+    fn test_fortran_order_and_data_types() {
+        let header = "{'descr': '<i4', 'fortran_order': True, 'shape': (2, 3), }";
+        let mut bytes = b"\x93NUMPY\x02\x00".to_vec();
+        bytes.extend((header.len() as u32).to_le_bytes());
+        bytes.extend(header.as_bytes());
+        // [[0, 1, 2], [3, 4, 5]] in column-major order
+        for value in [0i32, 3, 1, 4, 2, 5] {
+            bytes.extend(value.to_le_bytes());
+        }
+        assert_eq!(read_f64(bytes.as_slice()).unwrap(), (vec![2, 3], vec![0., 1., 2., 3., 4., 5.]));
+
+        let header = "{'descr': '>f8', 'fortran_order': False, 'shape': (1,), }";
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend((header.len() as u16).to_le_bytes());
+        bytes.extend(header.as_bytes());
+        assert!(matches!(read_f64(bytes.as_slice()), Err(NpyError::UnsupportedDataType(descr)) if descr == ">f8"));
+    }
+
+    #[test]
+    fn test_npz() {
+        let result = AnalyzeResult {
+            signal: vec![0.5; 100].into_boxed_slice(),
+            range: 10..90,
+            fs: 16000,
+            frame_period: 5.,
+            f0: Some(vec![0., 100., 120.].into_boxed_slice()),
+            temporal_positions: Some(vec![0., 0.005, 0.01].into_boxed_slice()),
+            spectrogram: Some(spectrogram(3, 9, 1.)),
+            aperiodicity: None,
+        };
+        let mut buffer = Cursor::new(Vec::new());
+        result.write_npz(&mut buffer).unwrap();
+        buffer.set_position(0);
+        let read = AnalyzeResult::read_npz(&mut buffer).unwrap();
+        assert_eq!(read.signal, result.signal);
+        assert_eq!(read.range, result.range);
+        assert_eq!(read.fs, result.fs);
+        assert_eq!(read.frame_period, result.frame_period);
+        assert_eq!(read.f0, result.f0);
+        assert_eq!(read.temporal_positions, result.temporal_positions);
+        let (read_sp, sp) = (read.spectrogram.unwrap(), result.spectrogram.unwrap());
+        assert_eq!(read_sp.lines().collect::<Vec<_>>(), sp.lines().collect::<Vec<_>>());
+        assert!(read.aperiodicity.is_none());
+
+        let with_range = |range: [f64; 2]| {
+            let mut buffer = Cursor::new(Vec::new());
+            let mut zip = ZipWriter::new(&mut buffer);
+            zip.start_file("x.npy", FileOptions::default()).unwrap();
+            write_f64(&mut zip, &[100], &[0.5; 100]).unwrap();
+            zip.start_file("range.npy", FileOptions::default()).unwrap();
+            write_f64(&mut zip, &[2], &range).unwrap();
+            zip.finish().unwrap();
+            drop(zip);
+            buffer.set_position(0);
+            AnalyzeResult::read_npz(&mut buffer)
+        };
+        // the range is read before fs
+        assert!(matches!(with_range([0., 100.]), Err(NpyError::MissingArray(keys::FS))));
+        for range in [[-1., 10.], [20., 10.], [0., 101.], [0., f64::NAN]] {
+            assert!(
+                matches!(with_range(range), Err(NpyError::World(WorldError::InvalidParameter("range")))),
+                "{range:?}"
+            );
+        }
+
+        let mut buffer = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut buffer);
+        zip.start_file("fs.npy", FileOptions::default()).unwrap();
+        write_i64(&mut zip, &[], &[16000]).unwrap();
+        zip.finish().unwrap();
+        drop(zip);
+        buffer.set_position(0);
+        assert!(matches!(
+            AnalyzeResult::read_npz(&mut buffer),
+            Err(NpyError::MissingArray(keys::FRAME_PERIOD))
+        ));
+
+        let with_fs_and_f0 = |fs: f64, f0_frames: usize| {
+            let mut buffer = Cursor::new(Vec::new());
+            let mut zip = ZipWriter::new(&mut buffer);
+            zip.start_file("fs.npy", FileOptions::default()).unwrap();
+            write_f64(&mut zip, &[], &[fs]).unwrap();
+            zip.start_file("frame_period.npy", FileOptions::default()).unwrap();
+            write_f64(&mut zip, &[], &[5.]).unwrap();
+            zip.start_file("f0.npy", FileOptions::default()).unwrap();
+            write_f64(&mut zip, &[f0_frames], &vec![100.; f0_frames]).unwrap();
+            zip.start_file("sp.npy", FileOptions::default()).unwrap();
+            spectrogram(3, 9, 1.).write_npy(&mut zip).unwrap();
+            zip.finish().unwrap();
+            drop(zip);
+            buffer.set_position(0);
+            AnalyzeResult::read_npz(&mut buffer)
+        };
+        assert_eq!(with_fs_and_f0(16000., 3).unwrap().fs, 16000);
+        for fs in [f64::NAN, 16000.5, 1e12, 0., -1.] {
+            assert!(
+                matches!(with_fs_and_f0(fs, 3), Err(NpyError::World(WorldError::InvalidSampleRate(_)))),
+                "{fs}"
+            );
+        }
+        assert!(matches!(
+            with_fs_and_f0(16000., 4),
+            Err(NpyError::World(WorldError::DifferentLength { expected: 4, actual: 3 }))
+        ));
+    }
+}
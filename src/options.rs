@@ -1,5 +1,45 @@
+use crate::error::{validate_fs, WorldError};
+use std::fmt::{Debug, Formatter};
 use std::mem::MaybeUninit;
-use world_sys::{InitializeCheapTrickOption, InitializeD4COption, InitializeHarvestOption};
+use world_sys::{
+    GetF0FloorForCheapTrick, GetFFTSizeForCheapTrick, InitializeCheapTrickOption, InitializeD4COption, InitializeDioOption, InitializeHarvestOption,
+};
+
+/// Largest fft_size [`fft_size_for_cheaptrick`] returns, to keep it in i32.
+const MAX_CHEAPTRICK_FFT_SIZE: i32 = 1 << 30;
+
+/// fft_size which CheapTrick requires to analyze f0 down to f0_floor at fs.
+///
+/// original: <https://github.com/mmorise/World/tree/v1.0.0/src/cheaptrick.cpp#L220-L223>
+pub fn fft_size_for_cheaptrick(fs: u32, f0_floor: f64) -> Result<i32, WorldError> {
+    let fs = validate_fs(fs)?;
+    if !(f0_floor.is_finite() && f0_floor > 0.) || 2. * (3. * fs as f64 / f0_floor + 1.) >= MAX_CHEAPTRICK_FFT_SIZE as f64 {
+        return Err(WorldError::InvalidF0Floor(f0_floor));
+    }
+    let option = world_sys::CheapTrickOption {
+        q1: 0.,
+        f0_floor,
+        fft_size: 0,
+    };
+    Ok(unsafe { GetFFTSizeForCheapTrick(fs, &option) })
+}
+
+/// The lowest f0 which CheapTrick can analyze with fft_size at fs.
+///
+/// original: <https://github.com/mmorise/World/tree/v1.0.0/src/cheaptrick.cpp#L226-L228>
+pub fn f0_floor_for_cheaptrick(fs: u32, fft_size: i32) -> Result<f64, WorldError> {
+    let fs = validate_fs(fs)?;
+    validate_cheaptrick_fft_size(fft_size)?;
+    Ok(unsafe { GetF0FloorForCheapTrick(fs, fft_size) })
+}
+
+/// fft_size must be a power of two of at least 4 for CheapTrick.
+pub(crate) fn validate_cheaptrick_fft_size(fft_size: i32) -> Result<(), WorldError> {
+    if fft_size < 4 || fft_size.count_ones() != 1 {
+        return Err(WorldError::InvalidFFTSize(fft_size));
+    }
+    Ok(())
+}
 
 /// Struct for CheapTrick
 pub struct CheapTrickOption(world_sys::CheapTrickOption);
@@ -36,11 +76,21 @@ impl CheapTrickOption {
     /// We strongly recommend not to change this value unless you have enough
     /// knowledge of the signal processing in CheapTrick.
     ///
+    /// This doesn't update fft_size. Use [`CheapTrickOption::set_f0_floor_and_fft_size`] to keep them consistent.
+    ///
     /// original: <https://github.com/mmorise/World/tree/v1.0.0/src/cheaptrick.cpp#L235-L236>
     pub fn set_f0_floor(&mut self, f0_floor: f64) {
         self.0.f0_floor = f0_floor;
     }
 
+    /// Set f0_floor and the fft_size determined from it and fs, as [`CheapTrickOption::new`] does.
+    pub fn set_f0_floor_and_fft_size(&mut self, fs: u32, f0_floor: f64) -> Result<(), WorldError> {
+        let fft_size = fft_size_for_cheaptrick(fs, f0_floor)?;
+        self.0.f0_floor = f0_floor;
+        self.0.fft_size = fft_size;
+        Ok(())
+    }
+
     pub fn fft_size(&self) -> i32 {
         self.0.fft_size
     }
@@ -53,11 +103,52 @@ impl CheapTrickOption {
         self.0.fft_size = fft_size;
     }
 
+    /// Check that CheapTrick can run with this option at `fs`.
+    ///
+    /// fft_size must be a power of two large enough for the window of f0_floor.
+    pub fn validate(&self, fs: i32) -> Result<(), WorldError> {
+        if !self.q1().is_finite() {
+            return Err(WorldError::InvalidParameter("q1"));
+        }
+        let required_fft_size = fft_size_for_cheaptrick(fs.try_into().unwrap_or(0), self.f0_floor())?;
+        let fft_size = self.fft_size();
+        if fft_size <= 0 || fft_size.count_ones() != 1 || fft_size < required_fft_size {
+            return Err(WorldError::InvalidFFTSize(fft_size));
+        }
+        Ok(())
+    }
+
     pub(crate) fn as_ptr(&self) -> *const world_sys::CheapTrickOption {
         &self.0
     }
 }
 
+impl Clone for CheapTrickOption {
+    fn clone(&self) -> Self {
+        CheapTrickOption(world_sys::CheapTrickOption {
+            q1: self.0.q1,
+            f0_floor: self.0.f0_floor,
+            fft_size: self.0.fft_size,
+        })
+    }
+}
+
+impl PartialEq for CheapTrickOption {
+    fn eq(&self, other: &Self) -> bool {
+        self.q1() == other.q1() && self.f0_floor() == other.f0_floor() && self.fft_size() == other.fft_size()
+    }
+}
+
+impl Debug for CheapTrickOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CheapTrickOption")
+            .field("q1", &self.q1())
+            .field("f0_floor", &self.f0_floor())
+            .field("fft_size", &self.fft_size())
+            .finish()
+    }
+}
+
 /// Struct for D4C
 pub struct D4COption(world_sys::D4COption);
 
@@ -88,6 +179,13 @@ impl D4COption {
         self.0.threshold = threshold;
     }
 
+    pub fn validate(&self) -> Result<(), WorldError> {
+        if !self.threshold().is_finite() {
+            return Err(WorldError::InvalidParameter("threshold"));
+        }
+        Ok(())
+    }
+
     pub(crate) fn as_ptr(&self) -> *const world_sys::D4COption {
         &self.0
     }
@@ -99,6 +197,34 @@ impl Default for D4COption {
     }
 }
 
+impl Clone for D4COption {
+    fn clone(&self) -> Self {
+        D4COption(world_sys::D4COption { threshold: self.0.threshold })
+    }
+}
+
+impl PartialEq for D4COption {
+    fn eq(&self, other: &Self) -> bool {
+        self.threshold() == other.threshold()
+    }
+}
+
+impl Debug for D4COption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("D4COption").field("threshold", &self.threshold()).finish()
+    }
+}
+
+fn validate_f0_range_and_frame_period(f0_floor: f64, f0_ceil: f64, frame_period: f64, fs: i32) -> Result<(), WorldError> {
+    if !(f0_floor.is_finite() && f0_ceil.is_finite() && 0. < f0_floor && f0_floor < f0_ceil && f0_ceil < fs as f64 / 2.) {
+        return Err(WorldError::InvalidF0Range { f0_floor, f0_ceil });
+    }
+    if !(frame_period.is_finite() && frame_period > 0.) {
+        return Err(WorldError::InvalidFramePeriod(frame_period));
+    }
+    Ok(())
+}
+
 /// Struct for Harvest
 pub struct HarvestOption(world_sys::HarvestOption);
 
@@ -143,6 +269,13 @@ impl HarvestOption {
         self.0.frame_period = frame_period;
     }
 
+    /// Check that Harvest can run with this option at `fs`.
+    ///
+    /// The f0 range must be positive and below the Nyquist frequency.
+    pub fn validate(&self, fs: i32) -> Result<(), WorldError> {
+        validate_f0_range_and_frame_period(self.f0_floor(), self.f0_ceil(), self.frame_period(), fs)
+    }
+
     pub(crate) fn as_ptr(&self) -> *const world_sys::HarvestOption {
         &self.0
     }
@@ -153,3 +286,444 @@ impl Default for HarvestOption {
         Self::new()
     }
 }
+
+impl Clone for HarvestOption {
+    fn clone(&self) -> Self {
+        HarvestOption(world_sys::HarvestOption {
+            f0_floor: self.0.f0_floor,
+            f0_ceil: self.0.f0_ceil,
+            frame_period: self.0.frame_period,
+        })
+    }
+}
+
+impl PartialEq for HarvestOption {
+    fn eq(&self, other: &Self) -> bool {
+        self.f0_floor() == other.f0_floor() && self.f0_ceil() == other.f0_ceil() && self.frame_period() == other.frame_period()
+    }
+}
+
+impl Debug for HarvestOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HarvestOption")
+            .field("f0_floor", &self.f0_floor())
+            .field("f0_ceil", &self.f0_ceil())
+            .field("frame_period", &self.frame_period())
+            .finish()
+    }
+}
+
+/// Struct for DIO
+pub struct DioOption(world_sys::DioOption);
+
+impl DioOption {
+    pub fn new() -> DioOption {
+        let option = unsafe {
+            let mut option = MaybeUninit::uninit();
+            InitializeDioOption(option.as_mut_ptr());
+            option.assume_init()
+        };
+        DioOption(option)
+    }
+
+    pub fn f0_floor(&self) -> f64 {
+        self.0.f0_floor
+    }
+
+    pub fn set_f0_floor(&mut self, f0_floor: f64) {
+        self.0.f0_floor = f0_floor;
+    }
+
+    pub fn f0_ceil(&self) -> f64 {
+        self.0.f0_ceil
+    }
+
+    pub fn set_f0_ceil(&mut self, f0_ceil: f64) {
+        self.0.f0_ceil = f0_ceil;
+    }
+
+    pub fn frame_period(&self) -> f64 {
+        self.0.frame_period
+    }
+
+    pub fn set_frame_period(&mut self, frame_period: f64) {
+        self.0.frame_period = frame_period;
+    }
+
+    pub fn channels_in_octave(&self) -> f64 {
+        self.0.channels_in_octave
+    }
+
+    /// The number of the candidates of f0 per octave, 2 by default.
+    pub fn set_channels_in_octave(&mut self, channels_in_octave: f64) {
+        self.0.channels_in_octave = channels_in_octave;
+    }
+
+    pub fn speed(&self) -> i32 {
+        self.0.speed
+    }
+
+    /// Decimation ratio of the signal in 1..=12, where 1 gives the highest accuracy and 12 the highest speed.
+    pub fn set_speed(&mut self, speed: i32) {
+        self.0.speed = speed;
+    }
+
+    pub fn allowed_range(&self) -> f64 {
+        self.0.allowed_range
+    }
+
+    /// Threshold of the fluctuation of f0 to be regarded as voiced, 0.1 by default.
+    pub fn set_allowed_range(&mut self, allowed_range: f64) {
+        self.0.allowed_range = allowed_range;
+    }
+
+    /// Check that DIO can run with this option at `fs`.
+    ///
+    /// The f0 range must be positive and below the Nyquist frequency, same as [`HarvestOption::validate`].
+    pub fn validate(&self, fs: i32) -> Result<(), WorldError> {
+        validate_f0_range_and_frame_period(self.f0_floor(), self.f0_ceil(), self.frame_period(), fs)?;
+        if !(self.channels_in_octave().is_finite() && self.channels_in_octave() > 0.) {
+            return Err(WorldError::InvalidParameter("channels_in_octave"));
+        }
+        if !(1..=12).contains(&self.speed()) {
+            return Err(WorldError::InvalidParameter("speed"));
+        }
+        if !(self.allowed_range().is_finite() && self.allowed_range() >= 0.) {
+            return Err(WorldError::InvalidParameter("allowed_range"));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const world_sys::DioOption {
+        &self.0
+    }
+}
+
+impl Default for DioOption {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for DioOption {
+    fn clone(&self) -> Self {
+        DioOption(world_sys::DioOption {
+            f0_floor: self.0.f0_floor,
+            f0_ceil: self.0.f0_ceil,
+            channels_in_octave: self.0.channels_in_octave,
+            frame_period: self.0.frame_period,
+            speed: self.0.speed,
+            allowed_range: self.0.allowed_range,
+        })
+    }
+}
+
+impl PartialEq for DioOption {
+    fn eq(&self, other: &Self) -> bool {
+        self.f0_floor() == other.f0_floor()
+            && self.f0_ceil() == other.f0_ceil()
+            && self.channels_in_octave() == other.channels_in_octave()
+            && self.frame_period() == other.frame_period()
+            && self.speed() == other.speed()
+            && self.allowed_range() == other.allowed_range()
+    }
+}
+
+impl Debug for DioOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DioOption")
+            .field("f0_floor", &self.f0_floor())
+            .field("f0_ceil", &self.f0_ceil())
+            .field("channels_in_octave", &self.channels_in_octave())
+            .field("frame_period", &self.frame_period())
+            .field("speed", &self.speed())
+            .field("allowed_range", &self.allowed_range())
+            .finish()
+    }
+}
+
+/// Estimator of f0, configured by [`HarvestOption`] or [`DioOption`] respectively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "kebab-case"))]
+pub enum F0Estimator {
+    /// Harvest, which is robust against noise and the default.
+    #[default]
+    Harvest,
+    /// DIO refined with StoneMask, which is much faster than Harvest but has more voicing errors.
+    Dio,
+}
+
+/// Named sets of the f0 range and the D4C threshold.
+///
+/// Apply one with [`crate::signal_analyzer::SignalAnalyzerBuilder::apply_preset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "kebab-case"))]
+pub enum Preset {
+    /// Default of WORLD.
+    Speech,
+    /// Wide f0 range from C2 to D6, and a lower threshold to keep sustained breathy notes voiced.
+    Singing,
+    /// Bass voices and vocal fry.
+    LowVoice,
+}
+
+impl Preset {
+    pub fn f0_floor(self) -> f64 {
+        match self {
+            Preset::Speech => 71.,
+            Preset::Singing => 65.,
+            Preset::LowVoice => 40.,
+        }
+    }
+
+    pub fn f0_ceil(self) -> f64 {
+        match self {
+            Preset::Speech => 800.,
+            Preset::Singing => 1200.,
+            Preset::LowVoice => 400.,
+        }
+    }
+
+    pub fn d4c_threshold(self) -> f64 {
+        match self {
+            Preset::Speech | Preset::LowVoice => 0.85,
+            Preset::Singing => 0.75,
+        }
+    }
+
+    pub fn harvest_option(self) -> HarvestOption {
+        let mut option = HarvestOption::new();
+        option.set_f0_floor(self.f0_floor());
+        option.set_f0_ceil(self.f0_ceil());
+        option
+    }
+
+    pub fn dio_option(self) -> DioOption {
+        let mut option = DioOption::new();
+        option.set_f0_floor(self.f0_floor());
+        option.set_f0_ceil(self.f0_ceil());
+        option
+    }
+
+    pub fn d4c_option(self) -> D4COption {
+        let mut option = D4COption::new();
+        option.set_threshold(self.d4c_threshold());
+        option
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_support {
+    use super::{CheapTrickOption, D4COption, DioOption, HarvestOption};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Missing q1 and f0_floor are filled with the default of WORLD.
+    /// fft_size is required, since it depends on fs.
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "CheapTrickOption", deny_unknown_fields)]
+    struct CheapTrickOptionRepr {
+        #[serde(default = "default_q1")]
+        q1: f64,
+        #[serde(default = "default_f0_floor")]
+        f0_floor: f64,
+        fft_size: i32,
+    }
+
+    // q1 and f0_floor of WORLD do not depend on fs
+    fn default_q1() -> f64 {
+        CheapTrickOption::new(16000).q1()
+    }
+
+    fn default_f0_floor() -> f64 {
+        CheapTrickOption::new(16000).f0_floor()
+    }
+
+    impl Serialize for CheapTrickOption {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            CheapTrickOptionRepr {
+                q1: self.q1(),
+                f0_floor: self.f0_floor(),
+                fft_size: self.fft_size(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for CheapTrickOption {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let CheapTrickOptionRepr { q1, f0_floor, fft_size } = CheapTrickOptionRepr::deserialize(deserializer)?;
+            Ok(CheapTrickOption(world_sys::CheapTrickOption { q1, f0_floor, fft_size }))
+        }
+    }
+
+    /// Missing fields are filled with the default of WORLD.
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "D4COption", default, deny_unknown_fields)]
+    struct D4COptionRepr {
+        threshold: f64,
+    }
+
+    impl Default for D4COptionRepr {
+        fn default() -> Self {
+            D4COptionRepr {
+                threshold: D4COption::new().threshold(),
+            }
+        }
+    }
+
+    impl Serialize for D4COption {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            D4COptionRepr { threshold: self.threshold() }.serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for D4COption {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let D4COptionRepr { threshold } = D4COptionRepr::deserialize(deserializer)?;
+            Ok(D4COption(world_sys::D4COption { threshold }))
+        }
+    }
+
+    /// Missing fields are filled with the default of WORLD.
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "HarvestOption", default, deny_unknown_fields)]
+    struct HarvestOptionRepr {
+        f0_floor: f64,
+        f0_ceil: f64,
+        frame_period: f64,
+    }
+
+    impl Default for HarvestOptionRepr {
+        fn default() -> Self {
+            let option = HarvestOption::new();
+            HarvestOptionRepr {
+                f0_floor: option.f0_floor(),
+                f0_ceil: option.f0_ceil(),
+                frame_period: option.frame_period(),
+            }
+        }
+    }
+
+    impl Serialize for HarvestOption {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            HarvestOptionRepr {
+                f0_floor: self.f0_floor(),
+                f0_ceil: self.f0_ceil(),
+                frame_period: self.frame_period(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for HarvestOption {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let HarvestOptionRepr {
+                f0_floor,
+                f0_ceil,
+                frame_period,
+            } = HarvestOptionRepr::deserialize(deserializer)?;
+            Ok(HarvestOption(world_sys::HarvestOption {
+                f0_floor,
+                f0_ceil,
+                frame_period,
+            }))
+        }
+    }
+
+    /// Missing fields are filled with the default of WORLD.
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "DioOption", default, deny_unknown_fields)]
+    struct DioOptionRepr {
+        f0_floor: f64,
+        f0_ceil: f64,
+        channels_in_octave: f64,
+        frame_period: f64,
+        speed: i32,
+        allowed_range: f64,
+    }
+
+    impl From<&DioOption> for DioOptionRepr {
+        fn from(option: &DioOption) -> Self {
+            DioOptionRepr {
+                f0_floor: option.f0_floor(),
+                f0_ceil: option.f0_ceil(),
+                channels_in_octave: option.channels_in_octave(),
+                frame_period: option.frame_period(),
+                speed: option.speed(),
+                allowed_range: option.allowed_range(),
+            }
+        }
+    }
+
+    impl Default for DioOptionRepr {
+        fn default() -> Self {
+            DioOptionRepr::from(&DioOption::new())
+        }
+    }
+
+    impl Serialize for DioOption {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            DioOptionRepr::from(self).serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for DioOption {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let DioOptionRepr {
+                f0_floor,
+                f0_ceil,
+                channels_in_octave,
+                frame_period,
+                speed,
+                allowed_range,
+            } = DioOptionRepr::deserialize(deserializer)?;
+            Ok(DioOption(world_sys::DioOption {
+                f0_floor,
+                f0_ceil,
+                channels_in_octave,
+                frame_period,
+                speed,
+                allowed_range,
+            }))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::super::*;
+
+        #[test]
+        fn test_serde() {
+            let mut harvest_option = HarvestOption::new();
+            harvest_option.set_f0_floor(40.);
+            let json = serde_json::to_string(&harvest_option).unwrap();
+            assert_eq!(json, r#"{"f0_floor":40.0,"f0_ceil":800.0,"frame_period":5.0}"#);
+            assert_eq!(serde_json::from_str::<HarvestOption>(&json).unwrap(), harvest_option);
+            assert_eq!(serde_json::from_str::<HarvestOption>(r#"{"f0_floor":40.0}"#).unwrap(), harvest_option);
+            assert!(serde_json::from_str::<HarvestOption>(r#"{"f0_flor":40.0}"#).is_err());
+
+            let cheaptrick_option = CheapTrickOption::new(16000);
+            let json = serde_json::to_string(&cheaptrick_option).unwrap();
+            assert_eq!(serde_json::from_str::<CheapTrickOption>(&json).unwrap(), cheaptrick_option);
+            assert_eq!(
+                serde_json::from_str::<CheapTrickOption>(r#"{"fft_size":1024}"#).unwrap(),
+                cheaptrick_option
+            );
+            assert!(serde_json::from_str::<CheapTrickOption>(r#"{"f0_floor":71.0}"#).is_err());
+
+            let dio_option = Preset::LowVoice.dio_option();
+            let json = serde_json::to_string(&dio_option).unwrap();
+            assert_eq!(serde_json::from_str::<DioOption>(&json).unwrap(), dio_option);
+            assert_eq!(
+                serde_json::from_str::<DioOption>(r#"{"f0_floor":40.0,"f0_ceil":400.0}"#).unwrap(),
+                dio_option
+            );
+
+            let d4c_option = Preset::Singing.d4c_option();
+            let json = serde_json::to_string(&d4c_option).unwrap();
+            assert_eq!(serde_json::from_str::<D4COption>(&json).unwrap(), d4c_option);
+
+            assert_eq!(serde_json::to_string(&Preset::LowVoice).unwrap(), r#""low-voice""#);
+        }
+    }
+}
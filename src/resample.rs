@@ -0,0 +1,131 @@
+//! Conversion of spectral parameters between sample rates and FFT sizes.
+//!
+//! The time axis is not changed, so f0 and frame_period can be passed to [`crate::synthesis::synthesis`] as they are
+//! together with the converted parameters and the new sample rate.
+//!
+//! Each new bin is interpolated from the two nearest bins of the original, without averaging the bins in between,
+//! so a coarser frequency axis, e.g. a smaller fft_size, may skip narrow peaks of the original.
+
+use crate::codec::fft_size_of;
+use crate::error::{validate_fs, WorldError};
+use crate::options::validate_cheaptrick_fft_size;
+use crate::spectrogram_like::SpectrogramLike;
+
+/// The band below the old Nyquist frequency whose mean level is used above it.
+const EXTRAPOLATION_BAND_RATIO: f64 = 0.9;
+
+/// Convert a spectral envelope analyzed at `fs` into one for `new_fs` and `new_fft_size`.
+///
+/// The frequency axis is interpolated linearly in the log power domain.
+/// Above the Nyquist frequency of `fs`, the mean log power of the top 10% band of the original envelope is used.
+/// Both the original and `new_fft_size` must be powers of two, as CheapTrick uses.
+pub fn resample_spectrogram(spectrogram: &SpectrogramLike<f64>, fs: u32, new_fs: u32, new_fft_size: i32) -> Result<SpectrogramLike<f64>, WorldError> {
+    resample(spectrogram, fs, new_fs, new_fft_size, |power| power.max(f64::MIN_POSITIVE).ln(), f64::exp)
+}
+
+/// Convert an aperiodicity analyzed at `fs` into one for `new_fs` and `new_fft_size`.
+///
+/// The frequency axis is interpolated linearly.
+/// Above the Nyquist frequency of `fs`, the mean aperiodicity of the top 10% band of the original is used.
+/// Both the original and `new_fft_size` must be powers of two, as D4C uses.
+pub fn resample_aperiodicity(
+    aperiodicity: &SpectrogramLike<f64>,
+    fs: u32,
+    new_fs: u32,
+    new_fft_size: i32,
+) -> Result<SpectrogramLike<f64>, WorldError> {
+    resample(aperiodicity, fs, new_fs, new_fft_size, |ap| ap, |ap| ap.clamp(0., 1.))
+}
+
+fn resample(
+    src: &SpectrogramLike<f64>,
+    fs: u32,
+    new_fs: u32,
+    new_fft_size: i32,
+    to_interpolation_domain: impl Fn(f64) -> f64,
+    from_interpolation_domain: impl Fn(f64) -> f64,
+) -> Result<SpectrogramLike<f64>, WorldError> {
+    validate_fs(fs)?;
+    validate_fs(new_fs)?;
+    validate_cheaptrick_fft_size(new_fft_size)?;
+    let src_bins = src.frequency_axis_size();
+    let src_fft_size = fft_size_of(src_bins)? as f64;
+    let dst_bins = new_fft_size as usize / 2 + 1;
+    let mut dst = SpectrogramLike::new(src.time_axis_size(), dst_bins);
+    let mut line_buffer = vec![0.; src_bins];
+    let band_start = ((src_bins - 1) as f64 * EXTRAPOLATION_BAND_RATIO) as usize;
+    for (src_line, dst_line) in src.lines().zip(dst.lines_mut()) {
+        line_buffer
+            .iter_mut()
+            .zip(src_line)
+            .for_each(|(buffer, &value)| *buffer = to_interpolation_domain(value));
+        let band = &line_buffer[band_start..];
+        let extrapolated = band.iter().sum::<f64>() / band.len() as f64;
+        for (bin, dst_value) in dst_line.iter_mut().enumerate() {
+            let frequency = bin as f64 * new_fs as f64 / new_fft_size as f64;
+            let position = frequency * src_fft_size / fs as f64;
+            let value = if position > (src_bins - 1) as f64 {
+                extrapolated
+            } else {
+                let index = (position.floor() as usize).min(src_bins - 1);
+                let next = (index + 1).min(src_bins - 1);
+                let fraction = position - index as f64;
+                line_buffer[index] * (1. - fraction) + line_buffer[next] * fraction
+            };
+            *dst_value = from_interpolation_domain(value);
+        }
+    }
+    Ok(dst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_identity() {
+        let spectrogram = SpectrogramLike::from_fn(3, 513, |_, i| 1e-3 * (1. + i as f64));
+        let resampled = resample_spectrogram(&spectrogram, 16000, 16000, 1024).unwrap();
+        for (line, resampled_line) in spectrogram.lines().zip(resampled.lines()) {
+            line.iter().zip(resampled_line).for_each(|(a, b)| assert!((a - b).abs() < 1e-12 * a));
+        }
+    }
+
+    #[test]
+    fn test_resample_upsampling() {
+        let spectrogram = SpectrogramLike::from_fn(2, 513, |_, i| if i < 256 { 1. } else { 1e-4 });
+        let resampled = resample_spectrogram(&spectrogram, 16000, 48000, 2048).unwrap();
+        assert_eq!(resampled.time_axis_size(), 2);
+        assert_eq!(resampled.frequency_axis_size(), 1025);
+        for line in resampled.lines() {
+            // 3000 Hz is bin 128 in 48kHz/2048 and bin 192 in 16kHz/1024
+            assert!((line[128] - 1.).abs() < 1e-12);
+            // above 8000 Hz
+            line[342..].iter().for_each(|&value| assert!((value - 1e-4).abs() < 1e-12));
+        }
+
+        let aperiodicity = SpectrogramLike::from_fn(2, 513, |_, i| i as f64 / 512.);
+        let resampled = resample_aperiodicity(&aperiodicity, 16000, 48000, 2048).unwrap();
+        for line in resampled.lines() {
+            assert!((line[128] - 192. / 512.).abs() < 1e-12);
+            line.iter().for_each(|value| assert!((0. ..=1.).contains(value)));
+        }
+    }
+
+    #[test]
+    fn test_resample_invalid() {
+        let spectrogram = SpectrogramLike::new(1, 513);
+        assert_eq!(
+            resample_spectrogram(&spectrogram, 0, 16000, 1024).err(),
+            Some(WorldError::InvalidSampleRate(0))
+        );
+        assert_eq!(
+            resample_spectrogram(&spectrogram, 16000, 16000, 1000).err(),
+            Some(WorldError::InvalidFFTSize(1000))
+        );
+        assert_eq!(
+            resample_aperiodicity(&SpectrogramLike::new(1, 500), 16000, 16000, 1024).err(),
+            Some(WorldError::InvalidFFTSize(998))
+        );
+    }
+}
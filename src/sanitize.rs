@@ -0,0 +1,282 @@
+//! Validation and repair of synthesis inputs.
+//!
+//! WORLD doesn't check its inputs, and NaN, negative F0 or aperiodicity outside \[0, 1\]
+//! make the synthesized signal silent, filled with NaN, or crash.
+
+use crate::spectrogram_like::SpectrogramLike;
+
+/// Smallest power of the spectral envelope after repair, same as kMySafeGuardMinimum in WORLD.
+pub const DEFAULT_SPECTROGRAM_FLOOR: f64 = 1e-12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Parameter {
+    F0,
+    Spectrogram,
+    Aperiodicity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IssueKind {
+    NaN,
+    Infinite,
+    Negative,
+    /// Zero power in the spectral envelope
+    Zero,
+    /// Aperiodicity outside \[0, 1\]
+    OutOfRange,
+}
+
+/// A problem found at `frame` (and `bin`, except for f0).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Issue {
+    pub parameter: Parameter,
+    pub frame: usize,
+    pub bin: Option<usize>,
+    pub kind: IssueKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SanitizeMode {
+    /// Fail the synthesis if any issue is found.
+    Reject,
+    /// Repair the inputs and synthesize.
+    Repair,
+}
+
+/// How invalid synthesis inputs are handled.
+///
+/// Repair makes f0 of invalid frames unvoiced (0),
+/// raises zero or negative power of the spectral envelope to `spectrogram_floor`, clamps aperiodicity into \[0, 1\],
+/// and fills NaN or infinity in the spectral envelope and aperiodicity with the nearest valid bin of the same frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SanitizeOption {
+    pub mode: SanitizeMode,
+    pub spectrogram_floor: f64,
+}
+
+impl SanitizeOption {
+    pub fn new(mode: SanitizeMode) -> SanitizeOption {
+        SanitizeOption {
+            mode,
+            spectrogram_floor: DEFAULT_SPECTROGRAM_FLOOR,
+        }
+    }
+}
+
+impl Default for SanitizeOption {
+    fn default() -> Self {
+        SanitizeOption::new(SanitizeMode::Repair)
+    }
+}
+
+fn find_issue(parameter: Parameter, value: f64) -> Option<IssueKind> {
+    if value.is_nan() {
+        return Some(IssueKind::NaN);
+    }
+    if value.is_infinite() {
+        return Some(IssueKind::Infinite);
+    }
+    match parameter {
+        Parameter::F0 | Parameter::Spectrogram if value < 0. => Some(IssueKind::Negative),
+        Parameter::Spectrogram if value == 0. => Some(IssueKind::Zero),
+        Parameter::Aperiodicity if !(0. ..=1.).contains(&value) => Some(IssueKind::OutOfRange),
+        _ => None,
+    }
+}
+
+/// Find all issues without modifying the inputs.
+pub fn check(f0: &[f64], spectrogram: &SpectrogramLike<f64>, aperiodicity: &SpectrogramLike<f64>) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    for (frame, &value) in f0.iter().enumerate() {
+        if let Some(kind) = find_issue(Parameter::F0, value) {
+            issues.push(Issue {
+                parameter: Parameter::F0,
+                frame,
+                bin: None,
+                kind,
+            });
+        }
+    }
+    for (parameter, spectrogram_like) in [(Parameter::Spectrogram, spectrogram), (Parameter::Aperiodicity, aperiodicity)] {
+        for (frame, line) in spectrogram_like.lines().enumerate() {
+            for (bin, &value) in line.iter().enumerate() {
+                if let Some(kind) = find_issue(parameter, value) {
+                    issues.push(Issue {
+                        parameter,
+                        frame,
+                        bin: Some(bin),
+                        kind,
+                    });
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// Repair the inputs in place and return the issues found.
+pub fn repair(
+    f0: &mut [f64],
+    spectrogram: &mut SpectrogramLike<f64>,
+    aperiodicity: &mut SpectrogramLike<f64>,
+    option: &SanitizeOption,
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    repair_into(f0, spectrogram, aperiodicity, option, &mut issues);
+    issues
+}
+
+/// Same as [`repair`], but appends the issues to `issues`, which doesn't allocate unless an issue is found.
+pub(crate) fn repair_into(
+    f0: &mut [f64],
+    spectrogram: &mut SpectrogramLike<f64>,
+    aperiodicity: &mut SpectrogramLike<f64>,
+    option: &SanitizeOption,
+    issues: &mut Vec<Issue>,
+) {
+    for (frame, value) in f0.iter_mut().enumerate() {
+        if let Some(kind) = find_issue(Parameter::F0, *value) {
+            issues.push(Issue {
+                parameter: Parameter::F0,
+                frame,
+                bin: None,
+                kind,
+            });
+            *value = 0.;
+        }
+    }
+    for (frame, line) in spectrogram.lines_mut().enumerate() {
+        repair_line(Parameter::Spectrogram, frame, line, option, |issue| issues.push(issue));
+    }
+    for (frame, line) in aperiodicity.lines_mut().enumerate() {
+        repair_line(Parameter::Aperiodicity, frame, line, option, |issue| issues.push(issue));
+    }
+}
+
+/// Same as [`repair`], but only repairs the frames of `issues`, which [`check`] found in the same inputs.
+pub(crate) fn repair_issues(
+    f0: &mut [f64],
+    spectrogram: &mut SpectrogramLike<f64>,
+    aperiodicity: &mut SpectrogramLike<f64>,
+    option: &SanitizeOption,
+    issues: &[Issue],
+) {
+    // check reports the issues of each line together
+    for issues in issues.chunk_by(|a, b| (a.parameter, a.frame) == (b.parameter, b.frame)) {
+        let Issue { parameter, frame, .. } = issues[0];
+        let line = match parameter {
+            Parameter::F0 => {
+                f0[frame] = 0.;
+                continue;
+            }
+            Parameter::Spectrogram => spectrogram.line_mut(frame),
+            Parameter::Aperiodicity => aperiodicity.line_mut(frame),
+        };
+        repair_line(parameter, frame, line.unwrap(), option, |_| ());
+    }
+}
+
+/// Repair a line of the spectral envelope or the aperiodicity, reporting each issue found.
+fn repair_line(parameter: Parameter, frame: usize, line: &mut [f64], option: &SanitizeOption, report: impl FnMut(Issue)) {
+    match parameter {
+        Parameter::Aperiodicity => repair_line_with(parameter, frame, line, |value| value.clamp(0., 1.), 1., report),
+        _ => repair_line_with(parameter, frame, line, |_| option.spectrogram_floor, option.spectrogram_floor, report),
+    }
+}
+
+fn repair_line_with(parameter: Parameter, frame: usize, line: &mut [f64], clamp: impl Fn(f64) -> f64, fallback: f64, mut report: impl FnMut(Issue)) {
+    let mut has_non_finite = false;
+    for (bin, value) in line.iter_mut().enumerate() {
+        let Some(kind) = find_issue(parameter, *value) else {
+            continue;
+        };
+        report(Issue {
+            parameter,
+            frame,
+            bin: Some(bin),
+            kind,
+        });
+        if value.is_finite() {
+            *value = clamp(*value);
+        } else {
+            has_non_finite = true;
+        }
+    }
+    if !has_non_finite {
+        return;
+    }
+    // every finite value is valid here
+    for bin in 0..line.len() {
+        if line[bin].is_finite() {
+            continue;
+        }
+        let nearest = (1..line.len())
+            .flat_map(|distance| [bin.checked_sub(distance), Some(bin + distance)])
+            .flatten()
+            .filter_map(|bin| line.get(bin))
+            .find(|value| value.is_finite());
+        line[bin] = nearest.copied().unwrap_or(fallback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repair() {
+        let mut f0 = [100., f64::NAN, -1., 0.];
+        let mut spectrogram = SpectrogramLike::new(4, 3);
+        let mut aperiodicity = SpectrogramLike::new(4, 3);
+        spectrogram.lines_mut().for_each(|line| line.copy_from_slice(&[1., 2., 3.]));
+        aperiodicity.lines_mut().for_each(|line| line.copy_from_slice(&[0.1, 0.2, 0.3]));
+        spectrogram.line_mut(1).unwrap().copy_from_slice(&[f64::NAN, 0., f64::INFINITY]);
+        spectrogram.line_mut(2).unwrap().copy_from_slice(&[f64::NAN, f64::NAN, f64::NAN]);
+        aperiodicity.line_mut(3).unwrap().copy_from_slice(&[-0.5, f64::NAN, 1.5]);
+
+        let issues = check(&f0, &spectrogram, &aperiodicity);
+        assert_eq!(issues.len(), 11);
+        assert_eq!(
+            issues[0],
+            Issue {
+                parameter: Parameter::F0,
+                frame: 1,
+                bin: None,
+                kind: IssueKind::NaN
+            }
+        );
+        assert!(issues.contains(&Issue {
+            parameter: Parameter::Spectrogram,
+            frame: 1,
+            bin: Some(1),
+            kind: IssueKind::Zero
+        }));
+        assert!(issues.contains(&Issue {
+            parameter: Parameter::Aperiodicity,
+            frame: 3,
+            bin: Some(2),
+            kind: IssueKind::OutOfRange
+        }));
+
+        let option = SanitizeOption::default();
+        let mut repaired_by_issues = (f0, spectrogram.clone(), aperiodicity.clone());
+        repair_issues(
+            &mut repaired_by_issues.0,
+            &mut repaired_by_issues.1,
+            &mut repaired_by_issues.2,
+            &option,
+            &issues,
+        );
+        assert_eq!(repair(&mut f0, &mut spectrogram, &mut aperiodicity, &option), issues);
+        assert_eq!(f0, [100., 0., 0., 0.]);
+        assert_eq!(spectrogram.line(1).unwrap(), [DEFAULT_SPECTROGRAM_FLOOR; 3]);
+        assert_eq!(spectrogram.line(2).unwrap(), [DEFAULT_SPECTROGRAM_FLOOR; 3]);
+        assert_eq!(aperiodicity.line(3).unwrap(), [0., 0., 1.]);
+        assert!(check(&f0, &spectrogram, &aperiodicity).is_empty());
+        // the issues found beforehand repair the same values
+        let values = |spectrogram_like: &SpectrogramLike<f64>| spectrogram_like.lines().flatten().copied().collect::<Vec<_>>();
+        assert_eq!(f0, repaired_by_issues.0);
+        assert_eq!(values(&spectrogram), values(&repaired_by_issues.1));
+        assert_eq!(values(&aperiodicity), values(&repaired_by_issues.2));
+    }
+}
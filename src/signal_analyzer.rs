@@ -1,9 +1,12 @@
-use crate::options::{CheapTrickOption, D4COption, HarvestOption};
+use crate::error::{validate_fs, validate_signal, WorldError};
+use crate::options::{CheapTrickOption, D4COption, DioOption, F0Estimator, HarvestOption, Preset};
 use crate::spectrogram_like::SpectrogramLike;
+use crate::synthesis::{synthesis_with_length, SynthesisError};
+use std::ops::Range;
 use std::sync::OnceLock;
-use world_sys::{CheapTrick, GetSamplesForHarvest, Harvest, D4C};
+use world_sys::{with_randn_state, CheapTrick, Dio, GetSamplesForHarvest, Harvest, RandnState, StoneMask, D4C};
 
-struct HarvestResult {
+struct F0Result {
     f0: Box<[f64]>,
     temporal_positions: Box<[f64]>,
 }
@@ -11,56 +14,204 @@ struct HarvestResult {
 pub struct SignalAnalyzer {
     signal: Box<[f64]>,
     fs: i32,
+    f0_estimator: F0Estimator,
     harvest_option: HarvestOption,
+    dio_option: DioOption,
     cheaptrick_option: CheapTrickOption,
     d4c_option: D4COption,
-    harvest_result: OnceLock<HarvestResult>,
+    couple_cheaptrick_option: bool,
+    range: Option<Range<usize>>,
+    f0_result: OnceLock<F0Result>,
     spectrogram: OnceLock<SpectrogramLike<f64>>,
     aperiodicity: OnceLock<SpectrogramLike<f64>>,
 }
 
 pub struct AnalyzeResult {
     pub signal: Box<[f64]>,
+    /// Range of `signal` which is analyzed
+    pub range: Range<usize>,
+    pub fs: i32,
+    pub frame_period: f64,
     pub f0: Option<Box<[f64]>>,
+    pub temporal_positions: Option<Box<[f64]>>,
     pub spectrogram: Option<SpectrogramLike<f64>>,
     pub aperiodicity: Option<SpectrogramLike<f64>>,
 }
 
+impl AnalyzeResult {
+    /// Synthesize a signal of exactly the same length as the analyzed signal.
+    ///
+    /// If the analysis is restricted to a range, the output starts at the first frame and ends at the end of the range.
+    ///
+    /// Returns [`SynthesisError::MissingParameter`] unless f0, spectrogram and aperiodicity are all calculated.
+    pub fn resynthesis(&self, seed: Option<u64>) -> Result<Vec<f64>, SynthesisError> {
+        let (Some(f0), Some(spectrogram), Some(aperiodicity)) = (&self.f0, &self.spectrogram, &self.aperiodicity) else {
+            return Err(SynthesisError::MissingParameter);
+        };
+        let first_position = self.temporal_positions.as_deref().and_then(<[f64]>::first).copied().unwrap_or(0.);
+        synthesis_with_length(
+            f0,
+            spectrogram,
+            aperiodicity,
+            None,
+            self.frame_period,
+            self.fs as u32,
+            seed,
+            None,
+            resynthesis_length(&self.range, first_position, self.fs),
+        )
+    }
+}
+
 pub struct SignalAnalyzerBuilder {
     fs: i32,
+    f0_estimator: F0Estimator,
     harvest_option: HarvestOption,
+    dio_option: DioOption,
     cheaptrick_option: CheapTrickOption,
     d4c_option: D4COption,
+    couple_cheaptrick_option: bool,
+    range: Option<Range<usize>>,
+    time_range: Option<Range<f64>>,
 }
 
 impl SignalAnalyzerBuilder {
     pub fn new(fs: u32) -> SignalAnalyzerBuilder {
-        let fs = fs.try_into().unwrap();
-        SignalAnalyzerBuilder {
+        SignalAnalyzerBuilder::try_new(fs).unwrap()
+    }
+
+    /// fs must be positive and fit in i32.
+    pub fn try_new(fs: u32) -> Result<SignalAnalyzerBuilder, WorldError> {
+        let fs = validate_fs(fs)?;
+        Ok(SignalAnalyzerBuilder {
             fs,
+            f0_estimator: F0Estimator::default(),
             harvest_option: HarvestOption::new(),
+            dio_option: DioOption::new(),
             cheaptrick_option: CheapTrickOption::new(fs),
             d4c_option: D4COption::new(),
-        }
+            couple_cheaptrick_option: true,
+            range: None,
+            time_range: None,
+        })
     }
 
     pub fn fs(&self) -> i32 {
         self.fs
     }
 
+    /// Change fs. The fft_size of CheapTrick is recomputed while it is coupled with the f0 estimator.
+    pub fn set_fs(&mut self, fs: u32) -> Result<(), WorldError> {
+        self.fs = validate_fs(fs)?;
+        self.sync_cheaptrick_option()
+    }
+
+    /// Set f0_floor of Harvest and DIO, and of CheapTrick together with its fft_size while it is coupled with the f0 estimator.
+    pub fn set_f0_floor(&mut self, f0_floor: f64) -> Result<(), WorldError> {
+        self.harvest_option.set_f0_floor(f0_floor);
+        self.dio_option.set_f0_floor(f0_floor);
+        self.sync_cheaptrick_option()
+    }
+
+    /// Set f0_ceil of Harvest and DIO.
+    pub fn set_f0_ceil(&mut self, f0_ceil: f64) {
+        self.harvest_option.set_f0_ceil(f0_ceil);
+        self.dio_option.set_f0_ceil(f0_ceil);
+    }
+
+    /// Set frame_period of Harvest and DIO.
+    pub fn set_frame_period(&mut self, frame_period: f64) {
+        self.harvest_option.set_frame_period(frame_period);
+        self.dio_option.set_frame_period(frame_period);
+    }
+
+    /// Set the f0 range of Harvest and DIO and the threshold of D4C from `preset`.
+    ///
+    /// CheapTrick follows the new f0_floor while it is coupled with the f0 estimator.
+    pub fn apply_preset(&mut self, preset: Preset) -> Result<(), WorldError> {
+        self.harvest_option.set_f0_floor(preset.f0_floor());
+        self.harvest_option.set_f0_ceil(preset.f0_ceil());
+        self.dio_option.set_f0_floor(preset.f0_floor());
+        self.dio_option.set_f0_ceil(preset.f0_ceil());
+        self.d4c_option.set_threshold(preset.d4c_threshold());
+        self.sync_cheaptrick_option()
+    }
+
+    /// Whether f0_floor and fft_size of CheapTrick follow f0_floor of the f0 estimator and fs.
+    ///
+    /// CheapTrick treats f0 below its f0_floor as unvoiced,
+    /// so it must not be higher than f0_floor of the f0 estimator to get correct spectral envelopes.
+    /// This is true by default, and [`SignalAnalyzerBuilder::decouple_cheaptrick_option`] sets it false.
+    pub fn couple_cheaptrick_option(&self) -> bool {
+        self.couple_cheaptrick_option
+    }
+
+    pub fn set_couple_cheaptrick_option(&mut self, couple: bool) -> Result<(), WorldError> {
+        self.couple_cheaptrick_option = couple;
+        self.sync_cheaptrick_option()
+    }
+
+    fn sync_cheaptrick_option(&mut self) -> Result<(), WorldError> {
+        if !self.couple_cheaptrick_option {
+            return Ok(());
+        }
+        self.cheaptrick_option.set_f0_floor_and_fft_size(self.fs as u32, self.f0_floor())
+    }
+
+    pub fn f0_estimator(&self) -> F0Estimator {
+        self.f0_estimator
+    }
+
+    /// Change the f0 estimator. CheapTrick follows its f0_floor while it is coupled with the f0 estimator.
+    pub fn set_f0_estimator(&mut self, f0_estimator: F0Estimator) -> Result<(), WorldError> {
+        self.f0_estimator = f0_estimator;
+        self.sync_cheaptrick_option()
+    }
+
+    /// f0_floor of the option of the f0 estimator.
+    pub fn f0_floor(&self) -> f64 {
+        match self.f0_estimator {
+            F0Estimator::Harvest => self.harvest_option.f0_floor(),
+            F0Estimator::Dio => self.dio_option.f0_floor(),
+        }
+    }
+
+    /// frame_period of the option of the f0 estimator, which gives the frames of all the results.
+    pub fn frame_period(&self) -> f64 {
+        match self.f0_estimator {
+            F0Estimator::Harvest => self.harvest_option.frame_period(),
+            F0Estimator::Dio => self.dio_option.frame_period(),
+        }
+    }
+
     pub fn harvest_option(&self) -> &HarvestOption {
         &self.harvest_option
     }
 
-    pub fn harvest_option_mut(&mut self) -> &mut HarvestOption {
-        &mut self.harvest_option
+    /// Replace the option of Harvest. CheapTrick follows its f0_floor while Harvest is the f0 estimator and they are coupled.
+    pub fn set_harvest_option(&mut self, harvest_option: HarvestOption) -> Result<(), WorldError> {
+        self.harvest_option = harvest_option;
+        self.sync_cheaptrick_option()
+    }
+
+    pub fn dio_option(&self) -> &DioOption {
+        &self.dio_option
+    }
+
+    /// Replace the option of DIO, same as [`SignalAnalyzerBuilder::set_harvest_option`].
+    pub fn set_dio_option(&mut self, dio_option: DioOption) -> Result<(), WorldError> {
+        self.dio_option = dio_option;
+        self.sync_cheaptrick_option()
     }
 
     pub fn cheaptrick_option(&self) -> &CheapTrickOption {
         &self.cheaptrick_option
     }
 
-    pub fn cheaptrick_option_mut(&mut self) -> &mut CheapTrickOption {
+    /// Decouple CheapTrick from the f0 estimator and fs, and return its option to change,
+    /// so that the changes are kept when f0_floor or fs changes afterwards.
+    pub fn decouple_cheaptrick_option(&mut self) -> &mut CheapTrickOption {
+        self.couple_cheaptrick_option = false;
         &mut self.cheaptrick_option
     }
 
@@ -72,55 +223,196 @@ impl SignalAnalyzerBuilder {
         &mut self.d4c_option
     }
 
+    /// Range of the samples to analyze, which a range set by [`SignalAnalyzerBuilder::set_time_range`] gives at the current fs.
+    pub fn range(&self) -> Option<Range<usize>> {
+        let to_sample = |time: f64| (time * self.fs as f64).round().max(0.) as usize;
+        self.range
+            .clone()
+            .or_else(|| self.time_range.as_ref().map(|range| to_sample(range.start)..to_sample(range.end)))
+    }
+
+    /// Restrict the analysis to the samples in `range`.
+    ///
+    /// Frames whose temporal positions are in the range are analyzed,
+    /// and the temporal positions are in the time base of the whole signal.
+    /// The f0 estimator runs on the range with a margin of [`SignalAnalyzer::range_margin`] around it.
+    pub fn set_range(&mut self, range: Option<Range<usize>>) {
+        self.range = range;
+        self.time_range = None;
+    }
+
+    /// Same as [`SignalAnalyzerBuilder::set_range`] with the range of `start..end` \[s\],
+    /// which is converted into samples at the fs on build.
+    pub fn set_time_range(&mut self, start: f64, end: f64) {
+        self.range = None;
+        self.time_range = Some(start..end);
+    }
+
+    fn validate_f0_option(&self) -> Result<(), WorldError> {
+        match self.f0_estimator {
+            F0Estimator::Harvest => self.harvest_option.validate(self.fs),
+            F0Estimator::Dio => self.dio_option.validate(self.fs),
+        }
+    }
+
+    /// Check the option of the f0 estimator and the others against each other and fs.
+    pub fn validate(&self) -> Result<(), WorldError> {
+        self.validate_f0_option()?;
+        self.cheaptrick_option.validate(self.fs)?;
+        self.d4c_option.validate()
+    }
+
+    /// Panics if the options or the signal are invalid. See [`SignalAnalyzerBuilder::try_build`].
     pub fn build(self, signal: Box<[f64]>) -> SignalAnalyzer {
         SignalAnalyzer::from_builder(self, signal)
     }
+
+    /// Reject invalid options and signals, same as [`SignalAnalyzer::try_from_builder`].
+    pub fn try_build(self, signal: Box<[f64]>) -> Result<SignalAnalyzer, WorldError> {
+        SignalAnalyzer::try_from_builder(self, signal)
+    }
+}
+
+/// Harvest runs with this many periods of f0_floor around the analyzed range, and at least [`MIN_RANGE_MARGIN`].
+const RANGE_MARGIN_PERIODS: f64 = 4.;
+/// Minimum margin around the analyzed range [s], which also covers the windows of CheapTrick and D4C.
+const MIN_RANGE_MARGIN: f64 = 0.1;
+
+/// Indices of the frames of Harvest on the whole signal whose temporal positions are in `range`.
+fn frames_in_range(range: &Range<usize>, fs: i32, frame_period: f64) -> Range<usize> {
+    // same as GetSamplesForHarvest
+    let frame_index = |sample: usize| 1000. * sample as f64 / fs as f64 / frame_period;
+    let first = frame_index(range.start).ceil() as usize;
+    let last = frame_index(range.end) as usize;
+    first..last + 1
+}
+
+/// Latest frame at or before `frame` whose temporal position is exactly on a sample, and the sample.
+///
+/// Frame 0 always is, so this may go back to the start of the signal if `samples_per_frame` has a long fraction.
+fn frame_on_sample(frame: usize, samples_per_frame: f64) -> (usize, usize) {
+    (0..=frame)
+        .rev()
+        .map(|frame| (frame, frame as f64 * samples_per_frame))
+        .find(|(_, sample)| (sample - sample.round()).abs() < 1e-6)
+        .map_or((0, 0), |(frame, sample)| (frame, sample.round() as usize))
+}
+
+fn validate_range(range: &Range<usize>, signal_len: usize, fs: i32, frame_period: f64) -> Result<(), WorldError> {
+    if range.start >= range.end || range.end > signal_len || frames_in_range(range, fs, frame_period).is_empty() {
+        return Err(WorldError::InvalidRange {
+            start: range.start,
+            end: range.end,
+        });
+    }
+    Ok(())
+}
+
+fn resynthesis_length(range: &Range<usize>, first_position: f64, fs: i32) -> usize {
+    range.end.saturating_sub((first_position * fs as f64).round() as usize)
+}
+
+/// `duration` [s] of the signal bounds the temporal positions, whose sample indices WORLD keeps in int,
+/// and the Nyquist frequency of `fs` bounds f0, whose window CheapTrick and D4C size in int.
+fn validate_frames(temporal_positions: &[f64], f0: &[f64], duration: f64, fs: i32) -> Result<(), WorldError> {
+    if temporal_positions.len() != f0.len() {
+        return Err(WorldError::DifferentLength {
+            expected: temporal_positions.len(),
+            actual: f0.len(),
+        });
+    }
+    if f0.is_empty() || f0.len() > i32::MAX as usize {
+        return Err(WorldError::InvalidParameter("number of frames"));
+    }
+    if !temporal_positions.iter().all(|t| (0. ..=duration).contains(t)) {
+        return Err(WorldError::InvalidParameter("temporal_positions"));
+    }
+    if !f0.iter().all(|f0| (0. ..fs as f64 / 2.).contains(f0)) {
+        return Err(WorldError::InvalidParameter("f0"));
+    }
+    Ok(())
 }
 
 impl SignalAnalyzer {
     pub fn from_builder(builder: SignalAnalyzerBuilder, signal: Box<[f64]>) -> SignalAnalyzer {
+        SignalAnalyzer::try_from_builder(builder, signal).unwrap()
+    }
+
+    /// Reject invalid options and signals, which WORLD doesn't check.
+    pub fn try_from_builder(mut builder: SignalAnalyzerBuilder, signal: Box<[f64]>) -> Result<SignalAnalyzer, WorldError> {
+        builder.validate_f0_option()?;
+        builder.sync_cheaptrick_option()?;
+        builder.validate()?;
+        validate_signal(&signal)?;
+        let range = builder.range();
+        if let Some(range) = &range {
+            validate_range(range, signal.len(), builder.fs, builder.frame_period())?;
+        }
         let SignalAnalyzerBuilder {
             fs,
+            f0_estimator,
             harvest_option,
+            dio_option,
             cheaptrick_option,
             d4c_option,
+            couple_cheaptrick_option,
+            ..
         } = builder;
-        assert!(signal.len() <= i32::MAX as usize);
-        SignalAnalyzer {
+        Ok(SignalAnalyzer {
             signal,
             fs,
+            f0_estimator,
             harvest_option,
+            dio_option,
             cheaptrick_option,
             d4c_option,
-            harvest_result: OnceLock::new(),
+            couple_cheaptrick_option,
+            range,
+            f0_result: OnceLock::new(),
             spectrogram: OnceLock::new(),
             aperiodicity: OnceLock::new(),
-        }
+        })
     }
 
     pub fn new(signal: Box<[f64]>, fs: u32) -> SignalAnalyzer {
-        assert!(signal.len() <= i32::MAX as usize);
-        let fs = fs.try_into().unwrap();
-        SignalAnalyzer {
-            signal,
-            fs,
-            harvest_option: HarvestOption::new(),
-            cheaptrick_option: CheapTrickOption::new(fs),
-            d4c_option: D4COption::new(),
-            harvest_result: OnceLock::new(),
-            spectrogram: OnceLock::new(),
-            aperiodicity: OnceLock::new(),
-        }
+        SignalAnalyzer::try_new(signal, fs).unwrap()
+    }
+
+    pub fn try_new(signal: Box<[f64]>, fs: u32) -> Result<SignalAnalyzer, WorldError> {
+        SignalAnalyzerBuilder::try_new(fs)?.try_build(signal)
     }
 
     pub fn fs(&self) -> i32 {
         self.fs
     }
 
+    pub fn f0_estimator(&self) -> F0Estimator {
+        self.f0_estimator
+    }
+
     pub fn harvest_option(&self) -> &HarvestOption {
         &self.harvest_option
     }
 
+    pub fn dio_option(&self) -> &DioOption {
+        &self.dio_option
+    }
+
+    /// frame_period of the option of the f0 estimator, which gives the frames of all the results.
+    pub fn frame_period(&self) -> f64 {
+        match self.f0_estimator {
+            F0Estimator::Harvest => self.harvest_option.frame_period(),
+            F0Estimator::Dio => self.dio_option.frame_period(),
+        }
+    }
+
+    fn f0_floor(&self) -> f64 {
+        match self.f0_estimator {
+            F0Estimator::Harvest => self.harvest_option.f0_floor(),
+            F0Estimator::Dio => self.dio_option.f0_floor(),
+        }
+    }
+
     pub fn cheaptrick_option(&self) -> &CheapTrickOption {
         &self.cheaptrick_option
     }
@@ -129,92 +421,544 @@ impl SignalAnalyzer {
         &self.d4c_option
     }
 
-    fn harvest_result(&self) -> &HarvestResult {
-        self.harvest_result.get_or_init(|| {
-            let samples = unsafe { GetSamplesForHarvest(self.fs, self.signal.len() as i32, self.harvest_option.frame_period()) };
-            let mut temporal_positions = vec![0.; samples as usize].into_boxed_slice();
-            let mut f0 = vec![0.; samples as usize].into_boxed_slice();
-            unsafe {
+    /// Replace the estimator of f0, which discards all calculated results.
+    ///
+    /// f0_floor and fft_size of CheapTrick follow the option of the new estimator
+    /// if they are coupled as [`SignalAnalyzerBuilder::couple_cheaptrick_option`].
+    pub fn set_f0_estimator(&mut self, f0_estimator: F0Estimator) -> Result<(), WorldError> {
+        self.replace_f0_estimation(f0_estimator, self.harvest_option.clone(), self.dio_option.clone())
+    }
+
+    /// Replace the option of Harvest, which discards all calculated results.
+    ///
+    /// f0_floor and fft_size of CheapTrick follow the new option while Harvest is the f0 estimator
+    /// if they are coupled as [`SignalAnalyzerBuilder::couple_cheaptrick_option`].
+    pub fn set_harvest_option(&mut self, harvest_option: HarvestOption) -> Result<(), WorldError> {
+        self.replace_f0_estimation(self.f0_estimator, harvest_option, self.dio_option.clone())
+    }
+
+    /// Replace the option of DIO, same as [`SignalAnalyzer::set_harvest_option`].
+    pub fn set_dio_option(&mut self, dio_option: DioOption) -> Result<(), WorldError> {
+        self.replace_f0_estimation(self.f0_estimator, self.harvest_option.clone(), dio_option)
+    }
+
+    fn replace_f0_estimation(&mut self, f0_estimator: F0Estimator, harvest_option: HarvestOption, dio_option: DioOption) -> Result<(), WorldError> {
+        let (f0_floor, frame_period) = match f0_estimator {
+            F0Estimator::Harvest => {
+                harvest_option.validate(self.fs)?;
+                (harvest_option.f0_floor(), harvest_option.frame_period())
+            }
+            F0Estimator::Dio => {
+                dio_option.validate(self.fs)?;
+                (dio_option.f0_floor(), dio_option.frame_period())
+            }
+        };
+        if let Some(range) = &self.range {
+            validate_range(range, self.signal.len(), self.fs, frame_period)?;
+        }
+        if self.couple_cheaptrick_option {
+            self.cheaptrick_option.set_f0_floor_and_fft_size(self.fs as u32, f0_floor)?;
+        }
+        self.f0_estimator = f0_estimator;
+        self.harvest_option = harvest_option;
+        self.dio_option = dio_option;
+        self.f0_result.take();
+        self.spectrogram.take();
+        self.aperiodicity.take();
+        Ok(())
+    }
+
+    /// Replace the option of CheapTrick, which discards the spectrogram,
+    /// and also the aperiodicity if fft_size is changed.
+    ///
+    /// This decouples CheapTrick from Harvest.
+    pub fn set_cheaptrick_option(&mut self, cheaptrick_option: CheapTrickOption) -> Result<(), WorldError> {
+        cheaptrick_option.validate(self.fs)?;
+        if cheaptrick_option.fft_size() != self.cheaptrick_option.fft_size() {
+            self.aperiodicity.take();
+        }
+        self.cheaptrick_option = cheaptrick_option;
+        self.couple_cheaptrick_option = false;
+        self.spectrogram.take();
+        Ok(())
+    }
+
+    /// Replace the option of D4C, which discards only the aperiodicity.
+    pub fn set_d4c_option(&mut self, d4c_option: D4COption) -> Result<(), WorldError> {
+        d4c_option.validate()?;
+        self.d4c_option = d4c_option;
+        self.aperiodicity.take();
+        Ok(())
+    }
+
+    /// Range of the signal which is analyzed.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone().unwrap_or(0..self.signal.len())
+    }
+
+    /// Margin \[s\] around the range given by [`SignalAnalyzerBuilder::set_range`] which the f0 estimator also analyzes.
+    pub fn range_margin(&self) -> f64 {
+        (RANGE_MARGIN_PERIODS / self.f0_floor()).max(MIN_RANGE_MARGIN)
+    }
+
+    fn f0_result(&self) -> &F0Result {
+        self.f0_result.get_or_init(|| {
+            let Some(range) = &self.range else {
+                return self.estimate_f0(&self.signal);
+            };
+            let frame_period = self.frame_period();
+            let samples_per_frame = frame_period * self.fs as f64 / 1000.;
+            let frames = frames_in_range(range, self.fs, frame_period);
+            let margin = (self.range_margin() * self.fs as f64).ceil() as usize;
+            // start the f0 estimator at a frame of the whole signal on a sample, so that its frames are on the same grid
+            let margin_first_frame = (range.start.saturating_sub(margin) as f64 / samples_per_frame).floor() as usize;
+            let (slice_first_frame, slice_start) = frame_on_sample(margin_first_frame, samples_per_frame);
+            let slice_end = (range.end + margin).min(self.signal.len());
+            let F0Result { f0, .. } = self.estimate_f0(&self.signal[slice_start..slice_end]);
+            let f0 = f0
+                .iter()
+                .skip(frames.start - slice_first_frame)
+                .take(frames.len())
+                .copied()
+                .collect::<Box<[f64]>>();
+            let temporal_positions = (frames.start..frames.start + f0.len())
+                .map(|frame| frame as f64 * frame_period / 1000.)
+                .collect();
+            F0Result { f0, temporal_positions }
+        })
+    }
+
+    fn estimate_f0(&self, signal: &[f64]) -> F0Result {
+        // GetSamplesForDIO gives the same number of frames
+        let samples = unsafe { GetSamplesForHarvest(self.fs, signal.len() as i32, self.frame_period()) };
+        let mut temporal_positions = vec![0.; samples as usize].into_boxed_slice();
+        let mut f0 = vec![0.; samples as usize].into_boxed_slice();
+        match self.f0_estimator {
+            F0Estimator::Harvest => unsafe {
                 Harvest(
-                    self.signal.as_ptr(),
-                    self.signal.len() as i32,
+                    signal.as_ptr(),
+                    signal.len() as i32,
                     self.fs,
                     self.harvest_option.as_ptr(),
                     temporal_positions.as_mut_ptr(),
                     f0.as_mut_ptr(),
                 );
-            }
-            HarvestResult { temporal_positions, f0 }
-        })
+            },
+            F0Estimator::Dio => unsafe {
+                let mut raw_f0 = vec![0.; samples as usize];
+                Dio(
+                    signal.as_ptr(),
+                    signal.len() as i32,
+                    self.fs,
+                    self.dio_option.as_ptr(),
+                    temporal_positions.as_mut_ptr(),
+                    raw_f0.as_mut_ptr(),
+                );
+                StoneMask(
+                    signal.as_ptr(),
+                    signal.len() as i32,
+                    self.fs,
+                    temporal_positions.as_ptr(),
+                    raw_f0.as_ptr(),
+                    samples,
+                    f0.as_mut_ptr(),
+                );
+            },
+        }
+        F0Result { temporal_positions, f0 }
     }
 
     pub fn f0(&self) -> &[f64] {
-        &self.harvest_result().f0
+        &self.f0_result().f0
     }
 
     pub fn temporal_positions(&self) -> &[f64] {
-        &self.harvest_result().temporal_positions
+        &self.f0_result().temporal_positions
     }
 
     pub fn spectrogram(&self) -> &SpectrogramLike<f64> {
         self.spectrogram.get_or_init(|| {
-            let HarvestResult { f0, temporal_positions } = self.harvest_result();
-            let mut spectrogram = SpectrogramLike::new(f0.len(), self.cheaptrick_option.fft_size() as usize / 2 + 1);
-            unsafe {
-                CheapTrick(
-                    self.signal.as_ptr(),
-                    self.signal.len() as i32,
-                    self.fs,
-                    temporal_positions.as_ptr(),
-                    f0.as_ptr(),
-                    f0.len() as i32,
-                    self.cheaptrick_option.as_ptr(),
-                    spectrogram.as_mut_ptr(),
-                );
-            }
-            spectrogram
+            let F0Result { f0, temporal_positions } = self.f0_result();
+            self.cheaptrick(temporal_positions, f0)
         })
     }
 
     pub fn aperiodicity(&self) -> &SpectrogramLike<f64> {
         self.aperiodicity.get_or_init(|| {
-            let HarvestResult { f0, temporal_positions } = self.harvest_result();
-            let mut aperiodicity = SpectrogramLike::new(f0.len(), self.cheaptrick_option.fft_size() as usize / 2 + 1);
-            unsafe {
-                D4C(
-                    self.signal.as_ptr(),
-                    self.signal.len() as i32,
-                    self.fs,
-                    temporal_positions.as_ptr(),
-                    f0.as_ptr(),
-                    f0.len() as i32,
-                    self.cheaptrick_option.fft_size(),
-                    self.d4c_option.as_ptr(),
-                    aperiodicity.as_mut_ptr(),
-                );
-            }
-            aperiodicity
+            let F0Result { f0, temporal_positions } = self.f0_result();
+            self.d4c(temporal_positions, f0)
         })
     }
 
+    /// Length of the whole signal [s].
+    fn duration(&self) -> f64 {
+        self.signal.len() as f64 / self.fs as f64
+    }
+
+    /// Spectral envelope at arbitrary `temporal_positions` \[s\] with caller-supplied `f0`, without running Harvest.
+    ///
+    /// The positions must be in the signal and f0 below the Nyquist frequency. The result is not cached.
+    pub fn spectrogram_at(&self, temporal_positions: &[f64], f0: &[f64]) -> Result<SpectrogramLike<f64>, WorldError> {
+        validate_frames(temporal_positions, f0, self.duration(), self.fs)?;
+        Ok(self.cheaptrick(temporal_positions, f0))
+    }
+
+    /// Aperiodicity at arbitrary `temporal_positions` \[s\] with caller-supplied `f0`, without running Harvest.
+    ///
+    /// The positions must be in the signal and f0 below the Nyquist frequency. The result is not cached.
+    pub fn aperiodicity_at(&self, temporal_positions: &[f64], f0: &[f64]) -> Result<SpectrogramLike<f64>, WorldError> {
+        validate_frames(temporal_positions, f0, self.duration(), self.fs)?;
+        Ok(self.d4c(temporal_positions, f0))
+    }
+
+    /// Spectral envelope of a single frame at `time` \[s\].
+    pub fn spectrum_at_time(&self, time: f64, f0: f64) -> Result<Box<[f64]>, WorldError> {
+        let spectrogram = self.spectrogram_at(&[time], &[f0])?;
+        Ok(spectrogram.line(0).unwrap().into())
+    }
+
+    /// Aperiodicity of a single frame at `time` \[s\].
+    pub fn aperiodicity_at_time(&self, time: f64, f0: f64) -> Result<Box<[f64]>, WorldError> {
+        let aperiodicity = self.aperiodicity_at(&[time], &[f0])?;
+        Ok(aperiodicity.line(0).unwrap().into())
+    }
+
+    fn cheaptrick(&self, temporal_positions: &[f64], f0: &[f64]) -> SpectrogramLike<f64> {
+        let mut spectrogram = SpectrogramLike::new(f0.len(), self.cheaptrick_option.fft_size() as usize / 2 + 1);
+        // CheapTrick and D4C add tiny noise to the waveform, so the generator is reset to keep the result deterministic
+        with_randn_state(&mut RandnState::default(), || unsafe {
+            CheapTrick(
+                self.signal.as_ptr(),
+                self.signal.len() as i32,
+                self.fs,
+                temporal_positions.as_ptr(),
+                f0.as_ptr(),
+                f0.len() as i32,
+                self.cheaptrick_option.as_ptr(),
+                spectrogram.as_mut_ptr(),
+            );
+        });
+        spectrogram
+    }
+
+    fn d4c(&self, temporal_positions: &[f64], f0: &[f64]) -> SpectrogramLike<f64> {
+        let mut aperiodicity = SpectrogramLike::new(f0.len(), self.cheaptrick_option.fft_size() as usize / 2 + 1);
+        with_randn_state(&mut RandnState::default(), || unsafe {
+            D4C(
+                self.signal.as_ptr(),
+                self.signal.len() as i32,
+                self.fs,
+                temporal_positions.as_ptr(),
+                f0.as_ptr(),
+                f0.len() as i32,
+                self.cheaptrick_option.fft_size(),
+                self.d4c_option.as_ptr(),
+                aperiodicity.as_mut_ptr(),
+            );
+        });
+        aperiodicity
+    }
+
     pub fn calc_all(&self) {
         self.spectrogram();
         self.aperiodicity();
     }
 
+    /// Synthesize a signal of exactly the same length as the analyzed signal from all parameters.
+    ///
+    /// If the analysis is restricted to a range, the output starts at the first frame and ends at the end of the range.
+    pub fn resynthesis(&self, seed: Option<u64>) -> Result<Vec<f64>, SynthesisError> {
+        let out_len = resynthesis_length(&self.range(), self.temporal_positions()[0], self.fs);
+        synthesis_with_length(
+            self.f0(),
+            self.spectrogram(),
+            self.aperiodicity(),
+            Some(self.cheaptrick_option.fft_size()),
+            self.frame_period(),
+            self.fs as u32,
+            seed,
+            None,
+            out_len,
+        )
+    }
+
     pub fn into_result(self) -> AnalyzeResult {
+        let range = self.range();
+        let frame_period = self.frame_period();
         let SignalAnalyzer {
             signal,
-            harvest_result,
+            fs,
+            f0_result,
             spectrogram,
             aperiodicity,
             ..
         } = self;
+        let (f0, temporal_positions) = f0_result
+            .into_inner()
+            .map(|F0Result { f0, temporal_positions }| (f0, temporal_positions))
+            .unzip();
         AnalyzeResult {
             signal,
-            f0: harvest_result.into_inner().map(|HarvestResult { f0, .. }| f0),
+            range,
+            fs,
+            frame_period,
+            f0,
+            temporal_positions,
             spectrogram: spectrogram.into_inner(),
             aperiodicity: aperiodicity.into_inner(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal() -> Box<[f64]> {
+        (0..16000).map(|i| (i as f64 * 0.05).sin()).collect()
+    }
+
+    #[test]
+    fn test_try_build() {
+        assert!(SignalAnalyzer::try_new(signal(), 16000).is_ok());
+        assert_eq!(SignalAnalyzerBuilder::try_new(0).err(), Some(WorldError::InvalidSampleRate(0)));
+        assert_eq!(SignalAnalyzer::try_new(Box::new([]), 16000).err(), Some(WorldError::EmptySignal));
+        assert_eq!(
+            SignalAnalyzer::try_new(Box::new([0., f64::NAN]), 16000).err(),
+            Some(WorldError::NonFiniteSignal)
+        );
+        assert!(matches!(
+            SignalAnalyzer::try_new(signal(), 1000).err(),
+            Some(WorldError::InvalidF0Range { .. })
+        ));
+
+        let mut builder = SignalAnalyzerBuilder::new(16000);
+        builder.set_f0_floor(500.).unwrap();
+        builder.set_f0_ceil(400.);
+        assert!(matches!(builder.try_build(signal()).err(), Some(WorldError::InvalidF0Range { .. })));
+
+        let mut builder = SignalAnalyzerBuilder::new(16000);
+        builder.set_frame_period(0.);
+        assert_eq!(builder.try_build(signal()).err(), Some(WorldError::InvalidFramePeriod(0.)));
+
+        let mut builder = SignalAnalyzerBuilder::new(16000);
+        builder.decouple_cheaptrick_option().set_fft_size(1000);
+        assert_eq!(builder.try_build(signal()).err(), Some(WorldError::InvalidFFTSize(1000)));
+
+        let mut builder = SignalAnalyzerBuilder::new(16000);
+        builder.decouple_cheaptrick_option().set_fft_size(256);
+        assert_eq!(builder.try_build(signal()).err(), Some(WorldError::InvalidFFTSize(256)));
+    }
+
+    #[test]
+    fn test_set_option_invalidates_dependent_cache() {
+        let mut analyzer = SignalAnalyzer::new(signal(), 16000);
+        let fill_cache = |analyzer: &mut SignalAnalyzer| {
+            let _ = analyzer.f0_result.set(F0Result {
+                f0: Box::new([]),
+                temporal_positions: Box::new([]),
+            });
+            let _ = analyzer.spectrogram.set(SpectrogramLike::new(1, 1));
+            let _ = analyzer.aperiodicity.set(SpectrogramLike::new(1, 1));
+        };
+        let cached = |analyzer: &SignalAnalyzer| {
+            (
+                analyzer.f0_result.get().is_some(),
+                analyzer.spectrogram.get().is_some(),
+                analyzer.aperiodicity.get().is_some(),
+            )
+        };
+
+        fill_cache(&mut analyzer);
+        let mut d4c_option = D4COption::new();
+        d4c_option.set_threshold(0.5);
+        analyzer.set_d4c_option(d4c_option).unwrap();
+        assert_eq!(analyzer.d4c_option().threshold(), 0.5);
+        assert_eq!(cached(&analyzer), (true, true, false));
+
+        fill_cache(&mut analyzer);
+        analyzer.set_cheaptrick_option(CheapTrickOption::new(16000)).unwrap();
+        assert_eq!(cached(&analyzer), (true, false, true));
+
+        fill_cache(&mut analyzer);
+        let mut cheaptrick_option = CheapTrickOption::new(16000);
+        cheaptrick_option.set_fft_size(2048);
+        analyzer.set_cheaptrick_option(cheaptrick_option).unwrap();
+        assert_eq!(cached(&analyzer), (true, false, false));
+
+        fill_cache(&mut analyzer);
+        let mut harvest_option = HarvestOption::new();
+        harvest_option.set_f0_ceil(10000.);
+        assert!(analyzer.set_harvest_option(harvest_option).is_err());
+        assert_eq!(cached(&analyzer), (true, true, true));
+        analyzer.set_harvest_option(HarvestOption::new()).unwrap();
+        assert_eq!(cached(&analyzer), (false, false, false));
+    }
+
+    #[test]
+    fn test_couple_cheaptrick_option() {
+        let mut builder = SignalAnalyzerBuilder::new(16000);
+        assert_eq!(builder.cheaptrick_option().fft_size(), 1024);
+        builder.set_f0_floor(40.).unwrap();
+        assert_eq!(builder.cheaptrick_option().f0_floor(), 40.);
+        assert_eq!(builder.cheaptrick_option().fft_size(), 2048);
+        builder.set_fs(48000).unwrap();
+        assert_eq!(builder.cheaptrick_option().fft_size(), 4096);
+
+        let mut harvest_option = HarvestOption::new();
+        harvest_option.set_f0_floor(71.);
+        builder.set_harvest_option(harvest_option).unwrap();
+        assert_eq!(builder.cheaptrick_option().fft_size(), 2048);
+        let analyzer = builder.build(signal());
+        assert_eq!(analyzer.cheaptrick_option().f0_floor(), 71.);
+        assert_eq!(analyzer.cheaptrick_option().fft_size(), 2048);
+
+        let mut builder = SignalAnalyzerBuilder::new(16000);
+        builder.decouple_cheaptrick_option().set_fft_size(4096);
+        builder.set_f0_floor(40.).unwrap();
+        assert!(!builder.couple_cheaptrick_option());
+        assert_eq!(builder.cheaptrick_option().fft_size(), 4096);
+        assert_eq!(builder.cheaptrick_option().f0_floor(), 71.);
+    }
+
+    #[test]
+    fn test_dio_option() {
+        let mut builder = SignalAnalyzerBuilder::new(16000);
+        builder.set_f0_estimator(F0Estimator::Dio).unwrap();
+        let mut dio_option = DioOption::new();
+        dio_option.set_frame_period(10.);
+        builder.set_dio_option(dio_option).unwrap();
+        builder.set_f0_floor(40.).unwrap();
+        // the option of Harvest is not used
+        let mut harvest_option = HarvestOption::new();
+        harvest_option.set_frame_period(0.);
+        builder.set_harvest_option(harvest_option).unwrap();
+        let mut analyzer = builder.build(signal());
+        assert_eq!(analyzer.cheaptrick_option().f0_floor(), 40.);
+        assert_eq!(analyzer.frame_period(), 10.);
+        assert_eq!(analyzer.temporal_positions()[1], 0.01);
+
+        let mut dio_option = DioOption::new();
+        dio_option.set_speed(0);
+        assert_eq!(analyzer.set_dio_option(dio_option).err(), Some(WorldError::InvalidParameter("speed")));
+        assert_eq!(
+            analyzer.set_f0_estimator(F0Estimator::Harvest).err(),
+            Some(WorldError::InvalidFramePeriod(0.))
+        );
+        analyzer.set_harvest_option(HarvestOption::new()).unwrap();
+        analyzer.set_f0_estimator(F0Estimator::Harvest).unwrap();
+        assert_eq!(analyzer.cheaptrick_option().f0_floor(), 71.);
+        assert_eq!(analyzer.into_result().frame_period, 5.);
+    }
+
+    #[test]
+    fn test_analyze_at() {
+        let analyzer = SignalAnalyzer::new(signal(), 16000);
+        assert_eq!(
+            analyzer.spectrogram_at(&[0.1, 0.2], &[100.]).err(),
+            Some(WorldError::DifferentLength { expected: 2, actual: 1 })
+        );
+        assert_eq!(
+            analyzer.spectrogram_at(&[], &[]).err(),
+            Some(WorldError::InvalidParameter("number of frames"))
+        );
+        for time in [f64::NAN, -0.1, 1.01, 1e300] {
+            assert_eq!(
+                analyzer.spectrum_at_time(time, 100.).err(),
+                Some(WorldError::InvalidParameter("temporal_positions"))
+            );
+        }
+        assert_eq!(analyzer.aperiodicity_at_time(0.5, -1.).err(), Some(WorldError::InvalidParameter("f0")));
+        assert_eq!(
+            analyzer.spectrogram_at(&[0.1, 0.2], &[100., 8000.]).err(),
+            Some(WorldError::InvalidParameter("f0"))
+        );
+        assert_eq!(analyzer.aperiodicity_at_time(0.5, 1e300).err(), Some(WorldError::InvalidParameter("f0")));
+    }
+
+    #[test]
+    fn test_analyze_at_matches_full_analysis() {
+        let analyzer = SignalAnalyzer::new(signal(), 16000);
+        let frames = [0, 57, 200];
+        let temporal_positions = frames.map(|frame| analyzer.temporal_positions()[frame]);
+        let f0 = frames.map(|frame| analyzer.f0()[frame]);
+        let spectrogram = analyzer.spectrogram_at(&temporal_positions, &f0).unwrap();
+        let aperiodicity = analyzer.aperiodicity_at(&temporal_positions, &f0).unwrap();
+        // only the tiny noise which CheapTrick and D4C add to the waveform differs
+        let assert_close = |expected: &[f64], actual: &[f64]| {
+            for (expected, actual) in expected.iter().zip(actual) {
+                assert!((expected - actual).abs() <= 1e-6 * expected.abs(), "{expected} != {actual}");
+            }
+        };
+        for (i, &frame) in frames.iter().enumerate() {
+            assert_close(analyzer.spectrogram().line(frame).unwrap(), spectrogram.line(i).unwrap());
+            assert_close(analyzer.aperiodicity().line(frame).unwrap(), aperiodicity.line(i).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_range() {
+        assert_eq!(frames_in_range(&(0..16000), 16000, 5.), 0..201);
+        assert_eq!(frames_in_range(&(100..1000), 16000, 5.), 2..13);
+        assert_eq!(frames_in_range(&(80..160), 16000, 5.), 1..3);
+        assert!(frames_in_range(&(81..159), 16000, 5.).is_empty());
+        assert_eq!(frames_in_range(&(0..44100), 44100, 5.), 0..201);
+        assert_eq!(resynthesis_length(&(0..16000), 0., 16000), 16000);
+        assert_eq!(resynthesis_length(&(100..1000), 0.01, 16000), 840);
+
+        let mut builder = SignalAnalyzerBuilder::new(16000);
+        builder.set_time_range(0.5, 0.75);
+        assert_eq!(builder.range(), Some(8000..12000));
+        builder.set_fs(32000).unwrap();
+        assert_eq!(builder.range(), Some(16000..24000));
+        builder.set_fs(16000).unwrap();
+        let analyzer = builder.build(signal());
+        assert_eq!(analyzer.range(), 8000..12000);
+
+        for range in [Range { start: 100, end: 50 }, 0..16001, 81..159] {
+            let mut builder = SignalAnalyzerBuilder::new(16000);
+            builder.set_range(Some(range.clone()));
+            assert_eq!(
+                builder.try_build(signal()).err(),
+                Some(WorldError::InvalidRange {
+                    start: range.start,
+                    end: range.end
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_range_on_frame_grid() {
+        // 220.5 samples per frame
+        assert_eq!(frame_on_sample(3, 220.5), (2, 441));
+        assert_eq!(frame_on_sample(4, 220.5), (4, 882));
+        assert_eq!(frame_on_sample(7, 80.), (7, 560));
+
+        let fs = 44100;
+        let signal = (0..fs)
+            .map(|i| 0.5 * (std::f64::consts::TAU * 150. * i as f64 / fs as f64).sin())
+            .collect::<Box<[f64]>>();
+        let whole = SignalAnalyzer::new(signal.clone(), fs as u32);
+        let mut builder = SignalAnalyzerBuilder::new(fs as u32);
+        // the margin starts at an odd frame
+        builder.set_range(Some(18000..27000));
+        let analyzer = builder.build(signal);
+        let frames = frames_in_range(&(18000..27000), fs, 5.);
+        assert_eq!(analyzer.temporal_positions(), &whole.temporal_positions()[frames.clone()]);
+        for (expected, actual) in whole.f0()[frames].iter().zip(analyzer.f0()) {
+            assert!((expected - actual).abs() < 1e-3 * expected, "{expected} != {actual}");
+        }
+    }
+
+    #[test]
+    fn test_apply_preset() {
+        let mut builder = SignalAnalyzerBuilder::new(16000);
+        builder.apply_preset(Preset::LowVoice).unwrap();
+        assert_eq!(builder.harvest_option(), &Preset::LowVoice.harvest_option());
+        assert_eq!(builder.d4c_option(), &Preset::LowVoice.d4c_option());
+        assert_eq!(builder.cheaptrick_option().f0_floor(), 40.);
+        assert_eq!(builder.cheaptrick_option().fft_size(), 2048);
+        builder.validate().unwrap();
+    }
+}
@@ -1,3 +1,5 @@
+use crate::error::WorldError;
+
 pub struct SpectrogramLike<T> {
     all: Box<[T]>,
     lines: Box<[*mut T]>,
@@ -5,13 +7,59 @@ pub struct SpectrogramLike<T> {
 
 impl<T: Default + Copy> SpectrogramLike<T> {
     pub fn new(time_axis_size: usize, frequency_axis_size: usize) -> SpectrogramLike<T> {
-        assert!(time_axis_size * frequency_axis_size > 0);
-        let mut all = vec![T::default(); time_axis_size * frequency_axis_size].into_boxed_slice();
+        SpectrogramLike::try_new(time_axis_size, frequency_axis_size).unwrap()
+    }
+
+    /// Both sizes must be non-zero.
+    pub fn try_new(time_axis_size: usize, frequency_axis_size: usize) -> Result<SpectrogramLike<T>, WorldError> {
+        let size = time_axis_size.checked_mul(frequency_axis_size).filter(|&size| size > 0);
+        let Some(size) = size else {
+            return Err(WorldError::InvalidSpectrogramSize {
+                time_axis_size,
+                frequency_axis_size,
+            });
+        };
+        let mut all = vec![T::default(); size].into_boxed_slice();
         let mut chunks_iter = all.chunks_exact_mut(frequency_axis_size);
         let lines = chunks_iter.by_ref().map(|slice| slice.as_mut_ptr()).collect::<Box<[_]>>();
         assert!(chunks_iter.into_remainder().is_empty());
         assert_eq!(lines.len(), time_axis_size);
-        SpectrogramLike { all, lines }
+        Ok(SpectrogramLike { all, lines })
+    }
+
+    pub fn from_fn(time_axis_size: usize, frequency_axis_size: usize, f: impl FnMut(usize, usize) -> T) -> SpectrogramLike<T> {
+        SpectrogramLike::try_from_fn(time_axis_size, frequency_axis_size, f).unwrap()
+    }
+
+    /// Element at each frame and bin is `f(frame, bin)`. Both sizes must be non-zero.
+    pub fn try_from_fn(
+        time_axis_size: usize,
+        frequency_axis_size: usize,
+        mut f: impl FnMut(usize, usize) -> T,
+    ) -> Result<SpectrogramLike<T>, WorldError> {
+        let mut spectrogram_like = SpectrogramLike::try_new(time_axis_size, frequency_axis_size)?;
+        for (frame, line) in spectrogram_like.lines_mut().enumerate() {
+            for (bin, value) in line.iter_mut().enumerate() {
+                *value = f(frame, bin);
+            }
+        }
+        Ok(spectrogram_like)
+    }
+
+    pub fn from_lines(lines: &[impl AsRef<[T]>]) -> SpectrogramLike<T> {
+        SpectrogramLike::try_from_lines(lines).unwrap()
+    }
+
+    /// Copy of `lines`, which must be non-empty and of the same non-zero length.
+    pub fn try_from_lines(lines: &[impl AsRef<[T]>]) -> Result<SpectrogramLike<T>, WorldError> {
+        let frequency_axis_size = lines.first().map_or(0, |line| line.as_ref().len());
+        if !lines.iter().all(|line| line.as_ref().len() == frequency_axis_size) {
+            return Err(WorldError::InvalidSpectrogramSize {
+                time_axis_size: lines.len(),
+                frequency_axis_size,
+            });
+        }
+        SpectrogramLike::try_from_fn(lines.len(), frequency_axis_size, |frame, bin| lines[frame].as_ref()[bin])
     }
 }
 
@@ -53,6 +101,15 @@ impl<T> SpectrogramLike<T> {
     }
 }
 
+impl<T: Clone> Clone for SpectrogramLike<T> {
+    fn clone(&self) -> Self {
+        let mut all = self.all.clone();
+        let f = self.frequency_axis_size();
+        let lines = all.chunks_exact_mut(f).map(|slice| slice.as_mut_ptr()).collect();
+        SpectrogramLike { all, lines }
+    }
+}
+
 #[cfg(any(feature = "ndarray", test))]
 mod conversion_ndarray {
     use ndarray::Array2;
@@ -110,6 +167,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_spectrogram_like_from_fn() {
+        let spec = SpectrogramLike::from_fn(2, 3, |frame, bin| frame * 10 + bin);
+        assert_eq!(spec.lines().collect::<Vec<_>>(), [[0, 1, 2], [10, 11, 12]]);
+        let spec = SpectrogramLike::from_lines(&[[0, 1, 2], [10, 11, 12]]);
+        assert_eq!(spec.lines().collect::<Vec<_>>(), [[0, 1, 2], [10, 11, 12]]);
+        assert!(SpectrogramLike::try_from_fn(0, 3, |_, _| 0).is_err());
+        assert_eq!(
+            SpectrogramLike::<i32>::try_from_lines(&[&[0, 1][..], &[2]]).err(),
+            Some(WorldError::InvalidSpectrogramSize {
+                time_axis_size: 2,
+                frequency_axis_size: 2
+            })
+        );
+        assert!(SpectrogramLike::<i32>::try_from_lines(&[] as &[[i32; 0]]).is_err());
+    }
+
+    #[test]
+    fn test_spectrogram_like_clone() {
+        let mut spec = SpectrogramLike::<u32>::new(3, 2);
+        spec.lines_mut().enumerate().for_each(|(i, line)| line.fill(i as u32));
+        let cloned = spec.clone();
+        spec.lines_mut().for_each(|line| line.fill(10));
+        assert_eq!(cloned.lines().collect::<Vec<_>>(), [[0, 0], [1, 1], [2, 2]]);
+        assert_eq!(unsafe { **cloned.as_ptr().offset(2) }, 2);
+    }
+
+    #[test]
+    fn test_spectrogram_like_try_new() {
+        assert!(SpectrogramLike::<f64>::try_new(2, 3).is_ok());
+        for (time_axis_size, frequency_axis_size) in [(0, 3), (2, 0), (usize::MAX, 2)] {
+            assert_eq!(
+                SpectrogramLike::<f64>::try_new(time_axis_size, frequency_axis_size).err(),
+                Some(WorldError::InvalidSpectrogramSize {
+                    time_axis_size,
+                    frequency_axis_size
+                })
+            );
+        }
+    }
+
     #[test]
     fn test_spectrogram_like_ndarray_conversion() {
         let raw_array1 = [[1., 2., 3.], [4., 5., 6.]];
@@ -0,0 +1,306 @@
+//! Raw features of SPTK and HTS style, which are used by the recipes of Merlin and HTS.
+//!
+//! Each stream is a headerless sequence of little endian float32 values, frame by frame:
+//! - lf0: log f0 of 1 dimension, with a sentinel for unvoiced frames
+//! - mgc: mel-cepstrum of `mgc_order + 1` dimensions
+//! - bap: band aperiodicity in decibels of [`number_of_aperiodicities`] dimensions, same as `code_aperiodicity` of pyworld
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::codec::{code_aperiodicity, decode_aperiodicity, number_of_aperiodicities};
+use crate::error::WorldError;
+use crate::mel_cepstrum::{alpha_for_fs, mel_cepstrum_to_spectrogram, spectrogram_to_mel_cepstrum};
+use crate::signal_analyzer::AnalyzeResult;
+use crate::spectrogram_like::SpectrogramLike;
+use crate::synthesis::SynthesisParameters;
+
+/// lf0 of unvoiced frames used by HTS and Merlin.
+pub const DEFAULT_UNVOICED_LF0: f64 = -1e10;
+/// 60 dimensions of mgc, which is the default of Merlin.
+pub const DEFAULT_MGC_ORDER: usize = 59;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SptkOption {
+    /// Order of the mel-cepstrum, which has `mgc_order + 1` dimensions including the 0th coefficient.
+    pub mgc_order: usize,
+    /// All-pass constant of the frequency warping, see [`alpha_for_fs`].
+    pub alpha: f64,
+    /// lf0 of unvoiced frames. On import, lf0 at or below this value is regarded as unvoiced.
+    pub unvoiced_lf0: f64,
+}
+
+impl SptkOption {
+    pub fn new(alpha: f64) -> SptkOption {
+        SptkOption {
+            mgc_order: DEFAULT_MGC_ORDER,
+            alpha,
+            unvoiced_lf0: DEFAULT_UNVOICED_LF0,
+        }
+    }
+
+    /// Option with the alpha of [`alpha_for_fs`].
+    pub fn for_fs(fs: u32) -> SptkOption {
+        SptkOption::new(alpha_for_fs(fs))
+    }
+}
+
+/// lf0, mgc and bap of an utterance, which have the same number of frames.
+pub struct SptkFeatures {
+    pub lf0: Vec<f64>,
+    pub mgc: SpectrogramLike<f64>,
+    pub bap: SpectrogramLike<f64>,
+}
+
+fn f0_to_lf0(f0: f64, unvoiced_lf0: f64) -> f64 {
+    if f0 > 0. {
+        f0.ln()
+    } else {
+        unvoiced_lf0
+    }
+}
+
+fn lf0_to_f0(lf0: f64, unvoiced_lf0: f64) -> f64 {
+    // the sentinel may be rounded by float32
+    if lf0.is_nan() || lf0 <= unvoiced_lf0 as f32 as f64 {
+        0.
+    } else {
+        lf0.exp()
+    }
+}
+
+impl SptkFeatures {
+    /// Features of f0, the spectral envelope and the aperiodicity of an analysis.
+    pub fn from_parameters(
+        f0: &[f64],
+        spectrogram: &SpectrogramLike<f64>,
+        aperiodicity: &SpectrogramLike<f64>,
+        fs: u32,
+        option: &SptkOption,
+    ) -> Result<SptkFeatures, WorldError> {
+        if f0.len() != spectrogram.time_axis_size() || f0.len() != aperiodicity.time_axis_size() {
+            return Err(WorldError::DifferentLength {
+                expected: f0.len(),
+                actual: spectrogram.time_axis_size().max(aperiodicity.time_axis_size()),
+            });
+        }
+        Ok(SptkFeatures {
+            lf0: f0.iter().map(|&f0| f0_to_lf0(f0, option.unvoiced_lf0)).collect(),
+            mgc: spectrogram_to_mel_cepstrum(spectrogram, option.mgc_order, option.alpha)?,
+            bap: code_aperiodicity(aperiodicity, fs)?,
+        })
+    }
+
+    /// Features of an analysis in which f0, the spectral envelope and the aperiodicity are calculated.
+    pub fn from_result(result: &AnalyzeResult, option: &SptkOption) -> Result<SptkFeatures, WorldError> {
+        let f0 = result.f0.as_deref().ok_or(WorldError::InvalidParameter("f0"))?;
+        let spectrogram = result.spectrogram.as_ref().ok_or(WorldError::InvalidParameter("spectrogram"))?;
+        let aperiodicity = result.aperiodicity.as_ref().ok_or(WorldError::InvalidParameter("aperiodicity"))?;
+        SptkFeatures::from_parameters(f0, spectrogram, aperiodicity, result.fs as u32, option)
+    }
+
+    /// f0, the spectral envelope and the aperiodicity of `fft_size / 2 + 1` bins to synthesize at `fs` and `frame_period`.
+    pub fn to_parameters(&self, fs: u32, frame_period: f64, fft_size: i32, option: &SptkOption) -> Result<SynthesisParameters, WorldError> {
+        if self.lf0.len() != self.mgc.time_axis_size() || self.lf0.len() != self.bap.time_axis_size() {
+            return Err(WorldError::DifferentLength {
+                expected: self.lf0.len(),
+                actual: self.mgc.time_axis_size().max(self.bap.time_axis_size()),
+            });
+        }
+        let fft_size_usize = usize::try_from(fft_size).map_err(|_| WorldError::InvalidFFTSize(fft_size))?;
+        let f0 = self.lf0.iter().map(|&lf0| lf0_to_f0(lf0, option.unvoiced_lf0)).collect();
+        let spectrogram = mel_cepstrum_to_spectrogram(&self.mgc, option.alpha, fft_size_usize)?;
+        let aperiodicity = decode_aperiodicity(&self.bap, fs, fft_size)?;
+        Ok(SynthesisParameters {
+            fs,
+            frame_period,
+            f0,
+            spectrogram,
+            aperiodicity,
+        })
+    }
+
+    /// Write the three streams into separate writers, which should be buffered, e.g. [`BufWriter`].
+    pub fn write(&self, lf0: impl Write, mgc: impl Write, bap: impl Write) -> io::Result<()> {
+        write_raw(lf0, &self.lf0)?;
+        write_raw(mgc, self.mgc.lines().flatten())?;
+        write_raw(bap, self.bap.lines().flatten())
+    }
+
+    /// Read the three streams, where mgc has `option.mgc_order + 1` dimensions and bap has [`number_of_aperiodicities`] of `fs`.
+    pub fn read(lf0: impl Read, mgc: impl Read, bap: impl Read, fs: u32, option: &SptkOption) -> Result<SptkFeatures, SptkError> {
+        let lf0 = read_raw(lf0)?;
+        let mgc = read_frames(mgc, option.mgc_order + 1)?;
+        let bap = read_frames(bap, number_of_aperiodicities(fs)?)?;
+        if mgc.time_axis_size() != lf0.len() || bap.time_axis_size() != lf0.len() {
+            return Err(WorldError::DifferentLength {
+                expected: lf0.len(),
+                actual: mgc.time_axis_size().max(bap.time_axis_size()),
+            }
+            .into());
+        }
+        Ok(SptkFeatures { lf0, mgc, bap })
+    }
+
+    /// Write `path` with the extensions `lf0`, `mgc` and `bap`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let create = |extension| File::create(path.with_extension(extension)).map(BufWriter::new);
+        self.write(create("lf0")?, create("mgc")?, create("bap")?)
+    }
+
+    /// Read `path` with the extensions `lf0`, `mgc` and `bap`.
+    pub fn load(path: impl AsRef<Path>, fs: u32, option: &SptkOption) -> Result<SptkFeatures, SptkError> {
+        let path = path.as_ref();
+        let open = |extension| File::open(path.with_extension(extension)).map(BufReader::new);
+        SptkFeatures::read(open("lf0")?, open("mgc")?, open("bap")?, fs, option)
+    }
+}
+
+#[derive(Debug)]
+pub enum SptkError {
+    Io(io::Error),
+    /// The length of a stream is not a multiple of the dimension.
+    InvalidLength {
+        len: usize,
+        dimension: usize,
+    },
+    World(WorldError),
+}
+
+impl Display for SptkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SptkError::Io(error) => write!(f, "{error}"),
+            SptkError::InvalidLength { len, dimension } => write!(f, "{len} values are not frames of {dimension} dimensions"),
+            SptkError::World(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for SptkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SptkError::Io(error) => Some(error),
+            SptkError::World(error) => Some(error),
+            SptkError::InvalidLength { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for SptkError {
+    fn from(value: io::Error) -> Self {
+        SptkError::Io(value)
+    }
+}
+
+impl From<WorldError> for SptkError {
+    fn from(value: WorldError) -> Self {
+        SptkError::World(value)
+    }
+}
+
+/// Write `values` as little endian float32 value by value, so `writer` should be buffered.
+pub fn write_raw<'a>(mut writer: impl Write, values: impl IntoIterator<Item = &'a f64>) -> io::Result<()> {
+    for &value in values {
+        writer.write_all(&(value as f32).to_le_bytes())?;
+    }
+    writer.flush()
+}
+
+/// Read little endian float32 values until the end.
+pub fn read_raw(mut reader: impl Read) -> io::Result<Vec<f64>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if !bytes.len().is_multiple_of(4) {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "incomplete float32 value"));
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()) as f64)
+        .collect())
+}
+
+fn read_frames(reader: impl Read, dimension: usize) -> Result<SpectrogramLike<f64>, SptkError> {
+    let values = read_raw(reader)?;
+    if dimension == 0 || !values.len().is_multiple_of(dimension) {
+        return Err(SptkError::InvalidLength {
+            len: values.len(),
+            dimension,
+        });
+    }
+    let mut frames = SpectrogramLike::try_new(values.len() / dimension, dimension)?;
+    for (frame, values) in frames.lines_mut().zip(values.chunks_exact(dimension)) {
+        frame.copy_from_slice(values);
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_features() {
+        let fs = 48000;
+        let f0 = [0., 100., 220.];
+        let mut spectrogram = SpectrogramLike::new(3, 513);
+        for (i, line) in spectrogram.lines_mut().enumerate() {
+            for (bin, power) in line.iter_mut().enumerate() {
+                *power = (-(bin as f64) / 100. - i as f64).exp() + 1e-6;
+            }
+        }
+        let mut aperiodicity = SpectrogramLike::new(3, 513);
+        for (bin, value) in aperiodicity.lines_mut().flatten().enumerate() {
+            *value = 0.01 + 0.9 * (bin % 513) as f64 / 512.;
+        }
+        let option = SptkOption {
+            mgc_order: 512,
+            alpha: 0.,
+            ..SptkOption::new(0.55)
+        };
+        let features = SptkFeatures::from_parameters(&f0, &spectrogram, &aperiodicity, fs, &option).unwrap();
+        assert_eq!(features.lf0[0], DEFAULT_UNVOICED_LF0);
+        assert!((features.lf0[1] - 100f64.ln()).abs() < 1e-12);
+        assert_eq!((features.mgc.time_axis_size(), features.mgc.frequency_axis_size()), (3, 513));
+        assert_eq!((features.bap.time_axis_size(), features.bap.frequency_axis_size()), (3, 5));
+
+        let (mut lf0, mut mgc, mut bap) = (Vec::new(), Vec::new(), Vec::new());
+        features.write(&mut lf0, &mut mgc, &mut bap).unwrap();
+        assert_eq!((lf0.len(), mgc.len(), bap.len()), (3 * 4, 3 * 513 * 4, 3 * 5 * 4));
+        let read = SptkFeatures::read(lf0.as_slice(), mgc.as_slice(), bap.as_slice(), fs, &option).unwrap();
+        let parameters = read.to_parameters(fs, 5., 1024, &option).unwrap();
+        assert_eq!((parameters.fs, parameters.frame_period), (fs, 5.));
+        assert_eq!(parameters.f0[0], 0.);
+        assert!((parameters.f0[2] / 220. - 1.).abs() < 1e-6);
+        // without warping and truncation, only float32 rounds the envelope
+        for (expected, actual) in spectrogram.lines().flatten().zip(parameters.spectrogram.lines().flatten()) {
+            assert!((actual / expected).ln().abs() < 1e-3, "{expected} != {actual}");
+        }
+        assert_eq!(parameters.aperiodicity.frequency_axis_size(), 513);
+
+        assert!(matches!(
+            SptkFeatures::read(lf0.as_slice(), &mgc[4..], bap.as_slice(), fs, &option),
+            Err(SptkError::InvalidLength { len: 1538, dimension: 513 })
+        ));
+
+        // the usual warped mel-cepstrum of 60 dimensions
+        let option = SptkOption::for_fs(fs);
+        let features = SptkFeatures::from_parameters(&f0, &spectrogram, &aperiodicity, fs, &option).unwrap();
+        let (mut lf0, mut mgc, mut bap) = (Vec::new(), Vec::new(), Vec::new());
+        features.write(&mut lf0, &mut mgc, &mut bap).unwrap();
+        assert_eq!(mgc.len(), 3 * 60 * 4);
+        let read = SptkFeatures::read(lf0.as_slice(), mgc.as_slice(), bap.as_slice(), fs, &option).unwrap();
+        for (expected, actual) in features.mgc.lines().flatten().zip(read.mgc.lines().flatten()) {
+            assert_eq!(*expected as f32 as f64, *actual);
+        }
+        // the truncation smooths the envelope, whose range is about 14 in the log domain
+        let parameters = read.to_parameters(fs, 5., 1024, &option).unwrap();
+        for (expected, actual) in spectrogram.lines().flatten().zip(parameters.spectrogram.lines().flatten()) {
+            assert!((actual / expected).ln().abs() < 0.1, "{expected} != {actual}");
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use crate::error::{validate_fs, WorldError};
+use crate::sanitize::{check, repair_into, repair_issues, Issue, SanitizeMode, SanitizeOption};
+use crate::signal_analyzer::AnalyzeResult;
 use crate::spectrogram_like::SpectrogramLike;
 use std::collections::VecDeque;
 use std::error::Error;
@@ -5,13 +8,23 @@ use std::fmt::{Display, Formatter};
 use std::iter::FusedIterator;
 use std::mem::MaybeUninit;
 use std::slice;
-use world_sys::{AddParameters, DestroySynthesizer, InitializeSynthesizer, IsLocked, RefreshSynthesizer, Synthesis, Synthesis2, WorldSynthesizer};
+use world_sys::{
+    with_randn_state, AddParameters, DestroySynthesizer, InitializeSynthesizer, IsLocked, RandnState, RefreshSynthesizer, Synthesis, Synthesis2,
+    WorldSynthesizer, RANDN_REPLACED,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SynthesisError {
     DifferentSizeInput,
     TooLargeValue,
     InvalidFFTSize,
+    BufferFull,
+    MissingParameter,
+    InvalidParameter,
+    /// Issues found in the inputs with [`SanitizeMode::Reject`].
+    InvalidInput(Vec<Issue>),
+    /// The linked WORLD does not use the seeded generator, e.g. with the `system-world` feature.
+    SeedUnsupported,
 }
 
 impl Display for SynthesisError {
@@ -20,12 +33,32 @@ impl Display for SynthesisError {
             SynthesisError::DifferentSizeInput => write!(f, "Different size input"),
             SynthesisError::TooLargeValue => write!(f, "Too large value"),
             SynthesisError::InvalidFFTSize => write!(f, "invalid fft size"),
+            SynthesisError::BufferFull => write!(f, "output buffer is full"),
+            SynthesisError::MissingParameter => write!(f, "missing parameter"),
+            SynthesisError::InvalidParameter => write!(f, "invalid parameter"),
+            SynthesisError::InvalidInput(issues) => match issues.first() {
+                Some(issue) => write!(f, "{} invalid values in the input, first {:?}", issues.len(), issue),
+                None => write!(f, "invalid value in the input"),
+            },
+            SynthesisError::SeedUnsupported => write!(f, "seed is not supported by the linked WORLD"),
         }
     }
 }
 
 impl Error for SynthesisError {}
 
+fn randn_state(seed: Option<u64>) -> RandnState {
+    seed.map_or(RandnState::INITIAL, RandnState::from_seed)
+}
+
+/// Synthesize a signal into `out`.
+///
+/// The noise exciting aperiodic components is generated from `seed`,
+/// so the same inputs and seed always give the same output, even when many syntheses run in parallel.
+/// `None` uses the initial state of WORLD's generator.
+/// A seed returns [`SynthesisError::SeedUnsupported`] if the linked WORLD keeps its own generator.
+/// WORLD interpolates between the frames, so at least 2 frames are required.
+#[allow(clippy::too_many_arguments)]
 pub fn synthesis_to(
     f0: &[f64],
     spectrogram: &SpectrogramLike<f64>,
@@ -33,6 +66,7 @@ pub fn synthesis_to(
     fft_size: Option<i32>,
     frame_period: f64,
     fs: u32,
+    seed: Option<u64>,
     out: &mut [f64],
 ) -> Result<(), SynthesisError> {
     if f0.len() != spectrogram.time_axis_size()
@@ -44,15 +78,21 @@ pub fn synthesis_to(
     if fs > i32::MAX as u32 || out.len() > i32::MAX as usize || f0.len() > i32::MAX as usize {
         return Err(SynthesisError::TooLargeValue);
     }
+    if f0.len() < 2 || fs == 0 || !(frame_period.is_finite() && frame_period > 0.) {
+        return Err(SynthesisError::InvalidParameter);
+    }
+    if seed.is_some() && !RANDN_REPLACED {
+        return Err(SynthesisError::SeedUnsupported);
+    }
     let fft_size = fft_size.ok_or(()).or_else(|_| {
         ((spectrogram.frequency_axis_size() - 1) * 2)
             .try_into()
             .map_err(|_| SynthesisError::TooLargeValue)
     })?;
-    if (fft_size / 2 + 1) as usize != spectrogram.frequency_axis_size() {
+    if fft_size <= 0 || fft_size.count_ones() != 1 || (fft_size / 2 + 1) as usize != spectrogram.frequency_axis_size() {
         return Err(SynthesisError::InvalidFFTSize);
     }
-    unsafe {
+    with_randn_state(&mut randn_state(seed), || unsafe {
         Synthesis(
             f0.as_ptr(),
             f0.len() as i32,
@@ -64,10 +104,25 @@ pub fn synthesis_to(
             out.len() as i32,
             out.as_mut_ptr(),
         )
-    }
+    });
     Ok(())
 }
 
+/// Length of the signal synthesized by [`synthesis`], same as WORLD's test program uses.
+///
+/// The last sample is placed at the last frame, so this can still differ from the length of the analyzed signal.
+/// Use [`synthesis_with_length`] to align the output with the analyzed signal exactly.
+///
+/// original: <https://github.com/mmorise/World/tree/v1.0.0/test/test.cpp#L433-L434>
+pub fn synthesis_length(f0_length: usize, frame_period: f64, fs: u32) -> usize {
+    if f0_length == 0 {
+        return 0;
+    }
+    ((f0_length - 1) as f64 * frame_period / 1000. * fs as f64) as usize + 1
+}
+
+/// `sanitize` enables the validation of the inputs. See [`crate::sanitize`].
+#[allow(clippy::too_many_arguments)]
 pub fn synthesis(
     f0: &[f64],
     spectrogram: &SpectrogramLike<f64>,
@@ -75,31 +130,224 @@ pub fn synthesis(
     fft_size: Option<i32>,
     frame_period: f64,
     fs: u32,
+    seed: Option<u64>,
+    sanitize: Option<&SanitizeOption>,
 ) -> Result<Vec<f64>, SynthesisError> {
-    let out_len = (f0.len() as f64 * frame_period * fs as f64 / 1000.).ceil() as usize;
+    let out_len = synthesis_length(f0.len(), frame_period, fs);
+    synthesis_with_length(f0, spectrogram, aperiodicity, fft_size, frame_period, fs, seed, sanitize, out_len)
+}
+
+/// Same as [`synthesis`], but the output has exactly `out_len` samples.
+///
+/// Pass the length of the analyzed signal to get the output aligned sample by sample with it.
+/// The issues repaired by `sanitize` are not returned, see [`synthesis_with_issues`] for them.
+#[allow(clippy::too_many_arguments)]
+pub fn synthesis_with_length(
+    f0: &[f64],
+    spectrogram: &SpectrogramLike<f64>,
+    aperiodicity: &SpectrogramLike<f64>,
+    fft_size: Option<i32>,
+    frame_period: f64,
+    fs: u32,
+    seed: Option<u64>,
+    sanitize: Option<&SanitizeOption>,
+    out_len: usize,
+) -> Result<Vec<f64>, SynthesisError> {
+    if let Some(sanitize) = sanitize {
+        return synthesis_with_issues(f0, spectrogram, aperiodicity, fft_size, frame_period, fs, seed, sanitize, out_len).map(|(out, _)| out);
+    }
     let mut out = vec![0.; out_len];
-    synthesis_to(f0, spectrogram, aperiodicity, fft_size, frame_period, fs, &mut out).map(move |_| out)
+    synthesis_to(f0, spectrogram, aperiodicity, fft_size, frame_period, fs, seed, &mut out)?;
+    Ok(out)
 }
 
+/// Same as [`synthesis_with_length`] with `sanitize`, but also returns the issues found in the inputs.
+///
+/// With [`SanitizeMode::Reject`], any issue fails the synthesis with [`SynthesisError::InvalidInput`] instead.
+/// With [`SanitizeMode::Repair`], the inputs are copied only if they have issues.
+#[allow(clippy::too_many_arguments)]
+pub fn synthesis_with_issues(
+    f0: &[f64],
+    spectrogram: &SpectrogramLike<f64>,
+    aperiodicity: &SpectrogramLike<f64>,
+    fft_size: Option<i32>,
+    frame_period: f64,
+    fs: u32,
+    seed: Option<u64>,
+    sanitize: &SanitizeOption,
+    out_len: usize,
+) -> Result<(Vec<f64>, Vec<Issue>), SynthesisError> {
+    let mut out = vec![0.; out_len];
+    let issues = check(f0, spectrogram, aperiodicity);
+    if issues.is_empty() {
+        synthesis_to(f0, spectrogram, aperiodicity, fft_size, frame_period, fs, seed, &mut out)?;
+    } else if sanitize.mode == SanitizeMode::Reject {
+        return Err(SynthesisError::InvalidInput(issues));
+    } else {
+        let (mut f0, mut spectrogram, mut aperiodicity) = (f0.to_vec(), spectrogram.clone(), aperiodicity.clone());
+        repair_issues(&mut f0, &mut spectrogram, &mut aperiodicity, sanitize, &issues);
+        synthesis_to(&f0, &spectrogram, &aperiodicity, fft_size, frame_period, fs, seed, &mut out)?;
+    }
+    Ok((out, issues))
+}
+
+/// Inputs of [`synthesis`] with the sample rate and the frame period, such as the result of morphing.
+#[derive(Clone)]
+pub struct SynthesisParameters {
+    pub fs: u32,
+    pub frame_period: f64,
+    pub f0: Vec<f64>,
+    pub spectrogram: SpectrogramLike<f64>,
+    pub aperiodicity: SpectrogramLike<f64>,
+}
+
+impl SynthesisParameters {
+    /// Returns [`SynthesisError::MissingParameter`] unless f0, spectrogram and aperiodicity are all calculated.
+    pub fn from_result(result: &AnalyzeResult) -> Result<SynthesisParameters, WorldError> {
+        let (Some(f0), Some(spectrogram), Some(aperiodicity)) = (&result.f0, &result.spectrogram, &result.aperiodicity) else {
+            return Err(SynthesisError::MissingParameter.into());
+        };
+        Ok(SynthesisParameters {
+            fs: result.fs as u32,
+            frame_period: result.frame_period,
+            f0: f0.to_vec(),
+            spectrogram: spectrogram.clone(),
+            aperiodicity: aperiodicity.clone(),
+        })
+    }
+
+    pub fn synthesis(&self, seed: Option<u64>, sanitize: Option<&SanitizeOption>) -> Result<Vec<f64>, SynthesisError> {
+        synthesis(
+            &self.f0,
+            &self.spectrogram,
+            &self.aperiodicity,
+            None,
+            self.frame_period,
+            self.fs,
+            seed,
+            sanitize,
+        )
+    }
+}
+
+/// Sample type which [`Synthesizer::render_into`] can write to.
+pub trait Sample: Copy {
+    fn from_f64(value: f64) -> Self;
+}
+
+impl Sample for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+}
+
+impl Sample for f32 {
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+
+const SYNTHESIZER_BUFFER_SIZE: i32 = 128;
+
 pub struct Synthesizer {
     synthesizer: WorldSynthesizer,
     queue: VecDeque<f64>,
+    capacity: Option<usize>,
+    randn_state: RandnState,
+    sanitize: Option<SanitizeOption>,
+    issues: Vec<Issue>,
 }
 
 impl Synthesizer {
     pub fn new(fs: u32, frame_period: f64, fft_size: i32) -> Synthesizer {
-        assert!(fs <= i32::MAX as u32);
+        Synthesizer::with_queue(fs, frame_period, fft_size, VecDeque::new(), None).unwrap()
+    }
+
+    /// Same as [`Synthesizer::try_with_capacity`], but panics on the errors.
+    pub fn with_capacity(fs: u32, frame_period: f64, fft_size: i32, capacity: usize) -> Synthesizer {
+        Synthesizer::try_with_capacity(fs, frame_period, fft_size, capacity).unwrap()
+    }
+
+    /// Create a synthesizer whose output queue is allocated once with `capacity` samples and never grows.
+    ///
+    /// [`Synthesizer::add`] returns [`SynthesisError::BufferFull`] instead of growing the queue,
+    /// so the output must be consumed with [`Synthesizer::render_into`] or [`Synthesizer::take_signal`] in time.
+    /// `capacity` must be at least the 128 samples WORLD synthesizes at once.
+    pub fn try_with_capacity(fs: u32, frame_period: f64, fft_size: i32, capacity: usize) -> Result<Synthesizer, WorldError> {
+        if capacity < SYNTHESIZER_BUFFER_SIZE as usize {
+            return Err(WorldError::InvalidParameter("capacity"));
+        }
+        Synthesizer::with_queue(fs, frame_period, fft_size, VecDeque::with_capacity(capacity), Some(capacity))
+    }
+
+    fn with_queue(fs: u32, frame_period: f64, fft_size: i32, queue: VecDeque<f64>, capacity: Option<usize>) -> Result<Synthesizer, WorldError> {
+        let fs = validate_fs(fs)?;
+        if !(frame_period.is_finite() && frame_period > 0.) {
+            return Err(WorldError::InvalidFramePeriod(frame_period));
+        }
+        if fft_size <= 0 || fft_size.count_ones() != 1 {
+            return Err(WorldError::InvalidFFTSize(fft_size));
+        }
         let synthesizer = unsafe {
             let mut synthesizer = MaybeUninit::uninit();
-            InitializeSynthesizer(fs as i32, frame_period, fft_size, 128, 1, synthesizer.as_mut_ptr());
+            InitializeSynthesizer(fs, frame_period, fft_size, SYNTHESIZER_BUFFER_SIZE, 1, synthesizer.as_mut_ptr());
             synthesizer.assume_init()
         };
-        Synthesizer {
+        Ok(Synthesizer {
             synthesizer,
-            queue: VecDeque::new(),
+            queue,
+            capacity,
+            randn_state: RandnState::INITIAL,
+            sanitize: None,
+            issues: Vec::new(),
+        })
+    }
+
+    /// Enable the validation of the inputs of [`Synthesizer::add`]. See [`crate::sanitize`].
+    ///
+    /// With [`SanitizeMode::Repair`], the inputs are repaired in place.
+    pub fn set_sanitize_option(&mut self, sanitize: Option<SanitizeOption>) {
+        self.sanitize = sanitize;
+    }
+
+    pub fn sanitize_option(&self) -> Option<&SanitizeOption> {
+        self.sanitize.as_ref()
+    }
+
+    /// Issues found in the inputs of the last [`Synthesizer::add`].
+    pub fn issues(&self) -> &[Issue] {
+        &self.issues
+    }
+
+    /// Reset the noise generator of this synthesizer.
+    ///
+    /// Each synthesizer owns its generator, so its output only depends on its inputs and the seed.
+    /// Returns [`SynthesisError::SeedUnsupported`] if the linked WORLD keeps its own generator.
+    pub fn set_seed(&mut self, seed: u64) -> Result<(), SynthesisError> {
+        if !RANDN_REPLACED {
+            return Err(SynthesisError::SeedUnsupported);
         }
+        self.randn_state = RandnState::from_seed(seed);
+        Ok(())
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Number of synthesized samples waiting to be taken.
+    pub fn available(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Upper bound of the number of samples one [`Synthesizer::add`] call with `f0_length` frames pushes into the queue.
+    fn max_output_len(&self, f0_length: usize) -> usize {
+        let buffer_size = self.synthesizer.buffer_size as usize;
+        let samples = (f0_length as f64 * self.synthesizer.frame_period * self.synthesizer.fs as f64 / 1000.).ceil() as usize;
+        (samples / buffer_size + 2) * buffer_size
     }
 
+    /// Synthesize the frames of `f0`, `spectrogram` and `aperiodicity`, which must have at least 2 frames.
     pub fn add(
         &mut self,
         f0: &mut [f64],
@@ -115,55 +363,97 @@ impl Synthesizer {
         if f0.len() > i32::MAX as usize {
             return Err(SynthesisError::TooLargeValue);
         }
+        if f0.len() < 2 {
+            return Err(SynthesisError::InvalidParameter);
+        }
         if (self.synthesizer.fft_size / 2 + 1) as usize != spectrogram.frequency_axis_size() {
             return Err(SynthesisError::InvalidFFTSize);
         }
-        while unsafe {
-            AddParameters(
-                f0.as_mut_ptr(),
-                f0.len() as i32,
-                spectrogram.as_mut_ptr(),
-                aperiodicity.as_mut_ptr(),
-                &mut self.synthesizer,
-            )
-        } == 0
-        {
+        if let Some(capacity) = self.capacity {
+            if capacity - self.queue.len() < self.max_output_len(f0.len()) {
+                return Err(SynthesisError::BufferFull);
+            }
+        }
+        self.issues.clear();
+        if let Some(option) = &self.sanitize {
+            match option.mode {
+                SanitizeMode::Reject => {
+                    self.issues = check(f0, spectrogram, aperiodicity);
+                    if !self.issues.is_empty() {
+                        return Err(SynthesisError::InvalidInput(self.issues.clone()));
+                    }
+                }
+                SanitizeMode::Repair => repair_into(f0, spectrogram, aperiodicity, option, &mut self.issues),
+            }
+        }
+        let Synthesizer {
+            synthesizer,
+            queue,
+            randn_state,
+            ..
+        } = self;
+        with_randn_state(randn_state, || {
+            while unsafe {
+                AddParameters(
+                    f0.as_mut_ptr(),
+                    f0.len() as i32,
+                    spectrogram.as_mut_ptr(),
+                    aperiodicity.as_mut_ptr(),
+                    synthesizer,
+                )
+            } == 0
+            {
+                unsafe {
+                    if Synthesis2(synthesizer) != 0 {
+                        queue.extend(
+                            slice::from_raw_parts(synthesizer.buffer, synthesizer.buffer_size as usize)
+                                .iter()
+                                .copied(),
+                        );
+                    }
+                    if IsLocked(synthesizer) != 0 {
+                        RefreshSynthesizer(synthesizer);
+                    }
+                }
+            }
             unsafe {
-                if Synthesis2(&mut self.synthesizer) != 0 {
-                    self.queue.extend(
-                        slice::from_raw_parts(self.synthesizer.buffer, self.synthesizer.buffer_size as usize)
+                while Synthesis2(synthesizer) != 0 {
+                    queue.extend(
+                        slice::from_raw_parts(synthesizer.buffer, synthesizer.buffer_size as usize)
                             .iter()
                             .copied(),
                     );
                 }
-                if IsLocked(&mut self.synthesizer) != 0 {
-                    RefreshSynthesizer(&mut self.synthesizer);
+                if IsLocked(synthesizer) != 0 {
+                    RefreshSynthesizer(synthesizer);
                 }
             }
-        }
-        unsafe {
-            while Synthesis2(&mut self.synthesizer) != 0 {
-                self.queue.extend(
-                    slice::from_raw_parts(self.synthesizer.buffer, self.synthesizer.buffer_size as usize)
-                        .iter()
-                        .copied(),
-                );
-            }
-            if IsLocked(&mut self.synthesizer) != 0 {
-                RefreshSynthesizer(&mut self.synthesizer);
-            }
-        }
+        });
         Ok(())
     }
 
-    pub fn take_signal(&mut self, len: usize) -> impl Iterator<Item = f64> + DoubleEndedIterator + ExactSizeIterator + FusedIterator + '_ {
+    pub fn take_signal(&mut self, len: usize) -> impl DoubleEndedIterator<Item = f64> + ExactSizeIterator + FusedIterator + '_ {
         let len = len.min(self.queue.len());
         self.queue.drain(..len)
     }
 
-    pub fn take_signal_all(&mut self) -> impl Iterator<Item = f64> + DoubleEndedIterator + ExactSizeIterator + FusedIterator + '_ {
+    pub fn take_signal_all(&mut self) -> impl DoubleEndedIterator<Item = f64> + ExactSizeIterator + FusedIterator + '_ {
         self.queue.drain(..)
     }
+
+    /// Move synthesized samples into `out` and return the number of written samples.
+    ///
+    /// This never allocates nor blocks, so it can be called from an audio callback.
+    /// [`Synthesizer::add`] with a fixed capacity does not grow the queue either,
+    /// but `AddParameters` of WORLD still allocates with C++ `new`, so it is not allocation-free.
+    /// Samples of `out` after the returned length are left untouched.
+    pub fn render_into<T: Sample>(&mut self, out: &mut [T]) -> usize {
+        let len = out.len().min(self.queue.len());
+        out.iter_mut()
+            .zip(self.queue.drain(..len))
+            .for_each(|(out, sample)| *out = T::from_f64(sample));
+        len
+    }
 }
 
 impl Drop for Synthesizer {
@@ -171,3 +461,63 @@ impl Drop for Synthesizer {
         unsafe { DestroySynthesizer(&mut self.synthesizer) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sanitize::{IssueKind, Parameter};
+
+    #[test]
+    fn test_synthesis_length() {
+        assert_eq!(synthesis_length(0, 5., 16000), 0);
+        assert_eq!(synthesis_length(201, 5., 16000), 16001);
+        assert_eq!(synthesis_length(200, 5., 44100), 43880);
+    }
+
+    #[test]
+    fn test_synthesis_rejects_single_frame() {
+        let spectrogram = SpectrogramLike::from_fn(1, 5, |_, _| 1.);
+        let aperiodicity = SpectrogramLike::from_fn(1, 5, |_, _| 0.5);
+        assert_eq!(
+            synthesis(&[100.], &spectrogram, &aperiodicity, None, 5., 16000, None, None),
+            Err(SynthesisError::InvalidParameter)
+        );
+    }
+
+    #[test]
+    fn test_synthesis_reports_issues() {
+        let spectrogram = SpectrogramLike::from_fn(2, 5, |_, _| 1.);
+        let aperiodicity = SpectrogramLike::from_fn(2, 5, |_, _| 0.5);
+        let option = SanitizeOption::new(SanitizeMode::Reject);
+        let issue = Issue {
+            parameter: Parameter::F0,
+            frame: 1,
+            bin: None,
+            kind: IssueKind::NaN,
+        };
+        assert_eq!(
+            synthesis(&[100., f64::NAN], &spectrogram, &aperiodicity, None, 5., 16000, None, Some(&option)),
+            Err(SynthesisError::InvalidInput(vec![issue]))
+        );
+    }
+
+    #[test]
+    fn test_try_with_capacity() {
+        assert_eq!(
+            Synthesizer::try_with_capacity(16000, 5., 1024, 64).err(),
+            Some(WorldError::InvalidParameter("capacity"))
+        );
+        assert_eq!(
+            Synthesizer::try_with_capacity(0, 5., 1024, 4096).err(),
+            Some(WorldError::InvalidSampleRate(0))
+        );
+        assert_eq!(
+            Synthesizer::try_with_capacity(16000, 0., 1024, 4096).err(),
+            Some(WorldError::InvalidFramePeriod(0.))
+        );
+        assert_eq!(
+            Synthesizer::try_with_capacity(16000, 5., 1000, 4096).err(),
+            Some(WorldError::InvalidFFTSize(1000))
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! Checks that the fixed-capacity synthesizer does not allocate through the Rust global allocator.
+//!
+//! Only the Rust allocations are counted. `AddParameters` of WORLD allocates with C++ `new`, which this test does not see.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use world::spectrogram_like::SpectrogramLike;
+use world::synthesis::Synthesizer;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn count() {
+    if COUNTING.with(Cell::get) {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations_in(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    COUNTING.with(|counting| counting.set(true));
+    f();
+    COUNTING.with(|counting| counting.set(false));
+    ALLOCATIONS.load(Ordering::SeqCst) - before
+}
+
+#[test]
+fn test_fixed_capacity_synthesizer_does_not_allocate() {
+    const FS: u32 = 16000;
+    const FRAME_PERIOD: f64 = 5.;
+    const FFT_SIZE: i32 = 1024;
+    const FRAMES: usize = 40;
+
+    let mut synthesizer = Synthesizer::try_with_capacity(FS, FRAME_PERIOD, FFT_SIZE, 16384).unwrap();
+    let mut f0 = vec![200.; FRAMES];
+    let mut spectrogram = SpectrogramLike::<f64>::new(FRAMES, FFT_SIZE as usize / 2 + 1);
+    spectrogram.lines_mut().for_each(|line| line.fill(1e-4));
+    let mut aperiodicity = SpectrogramLike::<f64>::new(FRAMES, FFT_SIZE as usize / 2 + 1);
+    aperiodicity.lines_mut().for_each(|line| line.fill(0.1));
+    let mut out_f32 = vec![0f32; 256];
+    let mut out_f64 = vec![0f64; 256];
+
+    let mut rendered = 0;
+    let allocations = allocations_in(|| {
+        synthesizer.add(&mut f0, &mut spectrogram, &mut aperiodicity).unwrap();
+        loop {
+            let f32_len = synthesizer.render_into(&mut out_f32);
+            let f64_len = synthesizer.render_into(&mut out_f64);
+            if f32_len == 0 && f64_len == 0 {
+                break;
+            }
+            rendered += f32_len + f64_len;
+        }
+    });
+    assert_eq!(allocations, 0);
+    assert!(rendered > 0);
+    assert_eq!(synthesizer.available(), 0);
+}
@@ -0,0 +1,83 @@
+//! FFT in WORLD and the analysis dominated by it.
+//!
+//! Compare the backends by running with and without the `rustfft` feature:
+//! `cargo bench -p world_sys` and `cargo bench -p world_sys --features rustfft`.
+
+use std::f64::consts::PI;
+use std::mem::MaybeUninit;
+use std::ptr;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use world_sys::*;
+
+const FS: i32 = 16000;
+const FFT_SIZES: &[usize] = &[1024, 2048, 4096];
+
+fn bench_real_fft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("real_fft");
+    for &fft_size in FFT_SIZES {
+        let mut waveform = (0..fft_size).map(|i| (i as f64 * 0.1).sin()).collect::<Vec<_>>();
+        let mut spectrum = vec![[0.; 2]; fft_size / 2 + 1];
+        unsafe {
+            let forward = fft_plan_dft_r2c_1d(fft_size as i32, waveform.as_mut_ptr(), spectrum.as_mut_ptr(), FFT_ESTIMATE);
+            let inverse = fft_plan_dft_c2r_1d(fft_size as i32, spectrum.as_mut_ptr(), waveform.as_mut_ptr(), FFT_ESTIMATE);
+            group.bench_function(BenchmarkId::new("forward", fft_size), |b| b.iter(|| fft_execute(ptr::read(&forward))));
+            group.bench_function(BenchmarkId::new("inverse", fft_size), |b| b.iter(|| fft_execute(ptr::read(&inverse))));
+            fft_destroy_plan(forward);
+            fft_destroy_plan(inverse);
+        }
+    }
+    group.finish();
+}
+
+fn bench_minimum_phase(c: &mut Criterion) {
+    let mut group = c.benchmark_group("minimum_phase");
+    for &fft_size in FFT_SIZES {
+        unsafe {
+            let mut minimum_phase = MaybeUninit::uninit();
+            InitializeMinimumPhaseAnalysis(fft_size as i32, minimum_phase.as_mut_ptr());
+            let mut minimum_phase = minimum_phase.assume_init();
+            for i in 0..=fft_size / 2 {
+                *minimum_phase.log_spectrum.add(i) = -(i as f64 / fft_size as f64);
+            }
+            group.bench_function(BenchmarkId::from_parameter(fft_size), |b| {
+                b.iter(|| GetMinimumPhaseSpectrum(&minimum_phase))
+            });
+            DestroyMinimumPhaseAnalysis(&mut minimum_phase);
+        }
+    }
+    group.finish();
+}
+
+fn bench_cheaptrick(c: &mut Criterion) {
+    let signal = (0..FS).map(|i| (2. * PI * 220. * i as f64 / FS as f64).sin()).collect::<Vec<_>>();
+    let temporal_positions = (0..200).map(|i| i as f64 * 0.005).collect::<Vec<_>>();
+    let f0 = vec![220.; temporal_positions.len()];
+    let option = unsafe {
+        let mut option = MaybeUninit::uninit();
+        InitializeCheapTrickOption(FS, option.as_mut_ptr());
+        option.assume_init()
+    };
+    let mut all = vec![0.; f0.len() * (option.fft_size as usize / 2 + 1)];
+    let mut spectrogram = all
+        .chunks_mut(option.fft_size as usize / 2 + 1)
+        .map(<[f64]>::as_mut_ptr)
+        .collect::<Vec<_>>();
+    c.bench_function("cheaptrick", |b| {
+        b.iter(|| unsafe {
+            CheapTrick(
+                signal.as_ptr(),
+                signal.len() as i32,
+                FS,
+                temporal_positions.as_ptr(),
+                f0.as_ptr(),
+                f0.len() as i32,
+                &option,
+                black_box(spectrogram.as_mut_ptr()),
+            )
+        })
+    });
+}
+
+criterion_group!(benches, bench_real_fft, bench_minimum_phase, bench_cheaptrick);
+criterion_main!(benches);
@@ -1,7 +1,3 @@
-use bindgen::callbacks::ParseCallbacks;
-use once_cell::sync::Lazy;
-use regex::Regex;
-use std::borrow::Cow;
 use std::env;
 use std::path::Path;
 
@@ -20,56 +16,135 @@ const WORLD_FILE_NAMES: &[&str] = &[
     "synthesisrealtime",
 ];
 
+/// Functions of fft.cpp, which are replaced with src/fft.rs by the `rustfft` feature.
+const FFT_FUNCTION_NAMES: &[&str] = &[
+    "fft_plan_dft_1d",
+    "fft_plan_dft_c2r_1d",
+    "fft_plan_dft_r2c_1d",
+    "fft_execute",
+    "fft_destroy_plan",
+];
+
+/// Optimization level of the vendored WORLD, independent of the cargo profile.
+const DEFAULT_OPT_LEVEL: &str = "3";
+
 fn main() {
     let world_src_dir = Path::new(WORLD_BASE_DIR).join("src");
-    generate_bindgen(&world_src_dir);
-    for file_name in WORLD_FILE_NAMES {
-        cc::Build::new()
-            .cpp(true)
-            .file(&world_src_dir.join(file_name).with_extension("cpp"))
-            .include(&world_src_dir)
-            .compile(file_name);
+    #[cfg(feature = "bindgen")]
+    bindings::generate_bindgen(&world_src_dir);
+    if cfg!(feature = "system") {
+        link_system_world();
+    } else {
+        build_vendored_world(&world_src_dir);
     }
 }
 
-fn generate_bindgen(world_src_dir: impl AsRef<Path>) {
-    let world_src_dir = world_src_dir.as_ref();
-    let world_header_dir = world_src_dir.join("world");
-    WORLD_FILE_NAMES
-        .iter()
-        .fold(bindgen::builder(), |builder, &entry| {
-            builder.header(world_header_dir.join(entry).with_extension("h").to_str().unwrap())
-        })
-        .clang_arg(format!("-I{}", world_src_dir.display()))
-        .clang_arg("-fparse-all-comments")
-        .derive_copy(false)
-        .parse_callbacks(Box::new(Cb))
-        .generate()
-        .unwrap()
-        .write_to_file(format!("{}/bindgen.rs", env::var("OUT_DIR").unwrap()))
-        .unwrap();
+/// Link WORLD installed in the system, e.g. `libworld.a` built with WORLD's CMakeLists.txt.
+///
+/// `WORLD_LIB_DIR` adds a search path and `WORLD_LIB_NAME` overrides the library name.
+fn link_system_world() {
+    println!("cargo:warning=the system WORLD uses its own randn, so the seeds of the synthesis are not supported");
+    println!("cargo:rerun-if-env-changed=WORLD_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=WORLD_LIB_NAME");
+    if let Ok(lib_dir) = env::var("WORLD_LIB_DIR") {
+        println!("cargo:rustc-link-search=native={lib_dir}");
+    }
+    let lib_name = env::var("WORLD_LIB_NAME").unwrap_or_else(|_| "world".to_owned());
+    println!("cargo:rustc-link-lib={lib_name}");
+    let target = env::var("TARGET").unwrap();
+    if target.contains("apple") || target.contains("freebsd") {
+        println!("cargo:rustc-link-lib=c++");
+    } else if !target.contains("msvc") {
+        println!("cargo:rustc-link-lib=stdc++");
+    }
 }
 
-#[derive(Debug)]
-struct Cb;
+/// Compile the vendored sources into one static library.
+///
+/// `WORLD_SYS_OPT_LEVEL` overrides the optimization level, and `CXXFLAGS` adds compiler flags as usual for cc.
+fn build_vendored_world(world_src_dir: &Path) {
+    println!("cargo:rerun-if-env-changed=WORLD_SYS_OPT_LEVEL");
+    println!("cargo:rerun-if-changed={}", world_src_dir.display());
+    let opt_level = env::var("WORLD_SYS_OPT_LEVEL").unwrap_or_else(|_| DEFAULT_OPT_LEVEL.to_owned());
+    let mut build = cc::Build::new();
+    build.cpp(true).include(world_src_dir).opt_level_str(&opt_level).warnings(false);
+
+    let source = |file_name: &str| world_src_dir.join(file_name).with_extension("cpp");
+    let mut objects = build.clone().file(source("matlabfunctions")).compile_intermediates();
+    let replace_fft = cfg!(feature = "rustfft");
+    if replace_fft {
+        // fft.cpp is kept under other names to test src/fft.rs against it
+        let mut fft = build.clone();
+        for name in FFT_FUNCTION_NAMES {
+            fft.define(name, format!("world_sys_ooura_{name}").as_str());
+        }
+        objects.extend(fft.file(source("fft")).compile_intermediates());
+    }
+    // randn is replaced with the thread local generator in src/randn.rs
+    build.define("randn", "world_sys_randn");
+    for &file_name in WORLD_FILE_NAMES {
+        if file_name != "matlabfunctions" && !(replace_fft && file_name == "fft") {
+            build.file(source(file_name));
+        }
+    }
+    build.objects(objects).compile("world");
+}
+
+#[cfg(feature = "bindgen")]
+mod bindings {
+    use super::WORLD_FILE_NAMES;
+    use bindgen::callbacks::ParseCallbacks;
+    use once_cell::sync::Lazy;
+    use regex::Regex;
+    use std::borrow::Cow;
+    use std::env;
+    use std::path::Path;
+
+    /// Also write the bindings to src/bindings.rs, which is used without the `bindgen` feature.
+    const UPDATE_BINDINGS_ENV: &str = "WORLD_SYS_UPDATE_BINDINGS";
+
+    pub fn generate_bindgen(world_src_dir: impl AsRef<Path>) {
+        println!("cargo:rerun-if-env-changed={UPDATE_BINDINGS_ENV}");
+        let world_src_dir = world_src_dir.as_ref();
+        let world_header_dir = world_src_dir.join("world");
+        let bindings = WORLD_FILE_NAMES
+            .iter()
+            .fold(bindgen::builder(), |builder, &entry| {
+                builder.header(world_header_dir.join(entry).with_extension("h").to_str().unwrap())
+            })
+            .clang_arg(format!("-I{}", world_src_dir.display()))
+            .clang_arg("-fparse-all-comments")
+            .derive_copy(false)
+            .parse_callbacks(Box::new(Cb))
+            .generate()
+            .unwrap();
+        bindings.write_to_file(format!("{}/bindgen.rs", env::var("OUT_DIR").unwrap())).unwrap();
+        if env::var_os(UPDATE_BINDINGS_ENV).is_some() {
+            bindings.write_to_file(Path::new("src").join("bindings.rs")).unwrap();
+        }
+    }
+
+    #[derive(Debug)]
+    struct Cb;
 
-impl ParseCallbacks for Cb {
-    fn process_comment(&self, comment: &str) -> Option<String> {
-        static HYPHEN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^\s*-{5,}\s*$").unwrap());
-        let comment = HYPHEN_REGEX.replace_all(comment, "");
-        static INOUT_MULTILINE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^(.*\S.*:.*\S.*)$\n *([^\s\w]|[^:\n]+$)").unwrap());
-        let comment = INOUT_MULTILINE_REGEX.replace_all(&comment, "$1 $2");
-        static INOUT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(In|Out)put:").unwrap());
-        let comment = INOUT_REGEX.replace_all(&comment, "\n$0");
-        static INOUT_BODY_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)((?:In|Out)put:\n(?:-.+\n)*)( *[^-\s]+)").unwrap());
-        let mut comment = comment.to_string();
-        while let Cow::Owned(s) = INOUT_BODY_REGEX.replace_all(&comment, "$1- $2") {
-            comment = s;
+    impl ParseCallbacks for Cb {
+        fn process_comment(&self, comment: &str) -> Option<String> {
+            static HYPHEN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^\s*-{5,}\s*$").unwrap());
+            let comment = HYPHEN_REGEX.replace_all(comment, "");
+            static INOUT_MULTILINE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^(.*\S.*:.*\S.*)$\n *([^\s\w]|[^:\n]+$)").unwrap());
+            let comment = INOUT_MULTILINE_REGEX.replace_all(&comment, "$1 $2");
+            static INOUT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(In|Out)put:").unwrap());
+            let comment = INOUT_REGEX.replace_all(&comment, "\n$0");
+            static INOUT_BODY_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)((?:In|Out)put:\n(?:-.+\n)*)( *[^-\s]+)").unwrap());
+            let mut comment = comment.to_string();
+            while let Cow::Owned(s) = INOUT_BODY_REGEX.replace_all(&comment, "$1- $2") {
+                comment = s;
+            }
+            static BRACKET_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\[\]]").unwrap());
+            let comment = BRACKET_REGEX.replace_all(&comment, r"\$0");
+            static URL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"https?://\w+(\.\w+)*(/[\w.]+)*").unwrap());
+            let comment = URL_REGEX.replace_all(&comment, "<$0>");
+            Some(comment.into_owned())
         }
-        static BRACKET_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\[\]]").unwrap());
-        let comment = BRACKET_REGEX.replace_all(&comment, r"\$0");
-        static URL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"https?://\w+(\.\w+)*(/[\w.]+)*").unwrap());
-        let comment = URL_REGEX.replace_all(&comment, "<$0>");
-        Some(comment.into_owned())
     }
 }
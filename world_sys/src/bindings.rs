@@ -0,0 +1,296 @@
+pub type fft_complex = [f64; 2];
+#[repr(C)]
+#[derive(Debug)]
+pub struct fft_plan {
+    pub n: ::std::os::raw::c_int,
+    pub sign: ::std::os::raw::c_int,
+    pub flags: ::std::os::raw::c_uint,
+    pub c_in: *mut fft_complex,
+    pub in_: *mut f64,
+    pub c_out: *mut fft_complex,
+    pub out: *mut f64,
+    pub input: *mut f64,
+    pub ip: *mut ::std::os::raw::c_int,
+    pub w: *mut f64,
+}
+pub const FFT_FORWARD: u32 = 1;
+pub const FFT_BACKWARD: u32 = 2;
+pub const FFT_ESTIMATE: u32 = 3;
+#[repr(C)]
+#[derive(Debug)]
+pub struct ForwardRealFFT {
+    pub fft_size: ::std::os::raw::c_int,
+    pub waveform: *mut f64,
+    pub spectrum: *mut fft_complex,
+    pub forward_fft: fft_plan,
+}
+#[repr(C)]
+#[derive(Debug)]
+pub struct InverseRealFFT {
+    pub fft_size: ::std::os::raw::c_int,
+    pub waveform: *mut f64,
+    pub spectrum: *mut fft_complex,
+    pub inverse_fft: fft_plan,
+}
+#[repr(C)]
+#[derive(Debug)]
+pub struct InverseComplexFFT {
+    pub fft_size: ::std::os::raw::c_int,
+    pub input: *mut fft_complex,
+    pub output: *mut fft_complex,
+    pub inverse_fft: fft_plan,
+}
+#[repr(C)]
+#[derive(Debug)]
+pub struct MinimumPhaseAnalysis {
+    pub fft_size: ::std::os::raw::c_int,
+    pub log_spectrum: *mut f64,
+    pub minimum_phase_spectrum: *mut fft_complex,
+    pub cepstrum: *mut fft_complex,
+    pub inverse_fft: fft_plan,
+    pub forward_fft: fft_plan,
+}
+#[repr(C)]
+#[derive(Debug)]
+pub struct CheapTrickOption {
+    pub q1: f64,
+    pub f0_floor: f64,
+    pub fft_size: ::std::os::raw::c_int,
+}
+#[repr(C)]
+#[derive(Debug)]
+pub struct D4COption {
+    pub threshold: f64,
+}
+#[repr(C)]
+#[derive(Debug)]
+pub struct DioOption {
+    pub f0_floor: f64,
+    pub f0_ceil: f64,
+    pub channels_in_octave: f64,
+    pub frame_period: f64,
+    pub speed: ::std::os::raw::c_int,
+    pub allowed_range: f64,
+}
+#[repr(C)]
+#[derive(Debug)]
+pub struct HarvestOption {
+    pub f0_floor: f64,
+    pub f0_ceil: f64,
+    pub frame_period: f64,
+}
+#[repr(C)]
+#[derive(Debug)]
+pub struct WorldSynthesizer {
+    pub fs: ::std::os::raw::c_int,
+    pub frame_period: f64,
+    pub buffer_size: ::std::os::raw::c_int,
+    pub number_of_pointers: ::std::os::raw::c_int,
+    pub fft_size: ::std::os::raw::c_int,
+    pub buffer: *mut f64,
+    pub current_pointer: ::std::os::raw::c_int,
+    pub i: ::std::os::raw::c_int,
+    pub dc_remover: *mut f64,
+    pub f0_length: *mut ::std::os::raw::c_int,
+    pub f0_origin: *mut ::std::os::raw::c_int,
+    pub spectrogram: *mut *mut *mut f64,
+    pub aperiodicity: *mut *mut *mut f64,
+    pub current_pointer2: ::std::os::raw::c_int,
+    pub head_pointer: ::std::os::raw::c_int,
+    pub synthesized_sample: ::std::os::raw::c_int,
+    pub handoff: ::std::os::raw::c_int,
+    pub handoff_phase: f64,
+    pub handoff_f0: f64,
+    pub last_location: ::std::os::raw::c_int,
+    pub cumulative_frame: ::std::os::raw::c_int,
+    pub current_frame: ::std::os::raw::c_int,
+    pub interpolated_vuv: *mut *mut f64,
+    pub pulse_locations: *mut *mut f64,
+    pub pulse_locations_index: *mut *mut ::std::os::raw::c_int,
+    pub pulse_locations_time_shift: *mut *mut f64,
+    pub number_of_pulses: *mut ::std::os::raw::c_int,
+    pub impulse_response: *mut f64,
+    pub minimum_phase: MinimumPhaseAnalysis,
+    pub inverse_real_fft: InverseRealFFT,
+    pub forward_real_fft: ForwardRealFFT,
+}
+extern "C" {
+    pub fn fft_plan_dft_1d(
+        n: ::std::os::raw::c_int,
+        in_: *mut fft_complex,
+        out: *mut fft_complex,
+        sign: ::std::os::raw::c_int,
+        flags: ::std::os::raw::c_uint,
+    ) -> fft_plan;
+    pub fn fft_plan_dft_c2r_1d(n: ::std::os::raw::c_int, in_: *mut fft_complex, out: *mut f64, flags: ::std::os::raw::c_uint) -> fft_plan;
+    pub fn fft_plan_dft_r2c_1d(n: ::std::os::raw::c_int, in_: *mut f64, out: *mut fft_complex, flags: ::std::os::raw::c_uint) -> fft_plan;
+    pub fn fft_execute(p: fft_plan);
+    pub fn fft_destroy_plan(p: fft_plan);
+    pub fn MyMaxInt(x: ::std::os::raw::c_int, y: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+    pub fn MyMaxDouble(x: f64, y: f64) -> f64;
+    pub fn MyMinInt(x: ::std::os::raw::c_int, y: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+    pub fn MyMinDouble(x: f64, y: f64) -> f64;
+    pub fn GetSuitableFFTSize(sample: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+    pub fn DCCorrection(input: *const f64, current_f0: f64, fs: ::std::os::raw::c_int, fft_size: ::std::os::raw::c_int, output: *mut f64);
+    pub fn LinearSmoothing(input: *const f64, width: f64, fs: ::std::os::raw::c_int, fft_size: ::std::os::raw::c_int, output: *mut f64);
+    pub fn NuttallWindow(y_length: ::std::os::raw::c_int, y: *mut f64);
+    pub fn InitializeForwardRealFFT(fft_size: ::std::os::raw::c_int, forward_real_fft: *mut ForwardRealFFT);
+    pub fn DestroyForwardRealFFT(forward_real_fft: *mut ForwardRealFFT);
+    pub fn InitializeInverseRealFFT(fft_size: ::std::os::raw::c_int, inverse_real_fft: *mut InverseRealFFT);
+    pub fn DestroyInverseRealFFT(inverse_real_fft: *mut InverseRealFFT);
+    pub fn InitializeInverseComplexFFT(fft_size: ::std::os::raw::c_int, inverse_complex_fft: *mut InverseComplexFFT);
+    pub fn DestroyInverseComplexFFT(inverse_complex_fft: *mut InverseComplexFFT);
+    pub fn InitializeMinimumPhaseAnalysis(fft_size: ::std::os::raw::c_int, minimum_phase: *mut MinimumPhaseAnalysis);
+    pub fn GetMinimumPhaseSpectrum(minimum_phase: *const MinimumPhaseAnalysis);
+    pub fn DestroyMinimumPhaseAnalysis(minimum_phase: *mut MinimumPhaseAnalysis);
+    pub fn CheapTrick(
+        x: *const f64,
+        x_length: ::std::os::raw::c_int,
+        fs: ::std::os::raw::c_int,
+        temporal_positions: *const f64,
+        f0: *const f64,
+        f0_length: ::std::os::raw::c_int,
+        option: *const CheapTrickOption,
+        spectrogram: *mut *mut f64,
+    );
+    pub fn InitializeCheapTrickOption(fs: ::std::os::raw::c_int, option: *mut CheapTrickOption);
+    pub fn GetFFTSizeForCheapTrick(fs: ::std::os::raw::c_int, option: *const CheapTrickOption) -> ::std::os::raw::c_int;
+    pub fn GetF0FloorForCheapTrick(fs: ::std::os::raw::c_int, fft_size: ::std::os::raw::c_int) -> f64;
+    pub fn GetNumberOfAperiodicities(fs: ::std::os::raw::c_int) -> ::std::os::raw::c_int;
+    pub fn CodeAperiodicity(
+        aperiodicity: *const *const f64,
+        f0_length: ::std::os::raw::c_int,
+        fs: ::std::os::raw::c_int,
+        fft_size: ::std::os::raw::c_int,
+        coded_aperiodicity: *mut *mut f64,
+    );
+    pub fn DecodeAperiodicity(
+        coded_aperiodicity: *const *const f64,
+        f0_length: ::std::os::raw::c_int,
+        fs: ::std::os::raw::c_int,
+        fft_size: ::std::os::raw::c_int,
+        aperiodicity: *mut *mut f64,
+    );
+    pub fn CodeSpectralEnvelope(
+        spectrogram: *const *const f64,
+        f0_length: ::std::os::raw::c_int,
+        fs: ::std::os::raw::c_int,
+        fft_size: ::std::os::raw::c_int,
+        number_of_dimensions: ::std::os::raw::c_int,
+        coded_spectral_envelope: *mut *mut f64,
+    );
+    pub fn DecodeSpectralEnvelope(
+        coded_spectral_envelope: *const *const f64,
+        f0_length: ::std::os::raw::c_int,
+        fs: ::std::os::raw::c_int,
+        fft_size: ::std::os::raw::c_int,
+        number_of_dimensions: ::std::os::raw::c_int,
+        spectrogram: *mut *mut f64,
+    );
+    pub fn D4C(
+        x: *const f64,
+        x_length: ::std::os::raw::c_int,
+        fs: ::std::os::raw::c_int,
+        temporal_positions: *const f64,
+        f0: *const f64,
+        f0_length: ::std::os::raw::c_int,
+        fft_size: ::std::os::raw::c_int,
+        option: *const D4COption,
+        aperiodicity: *mut *mut f64,
+    );
+    pub fn InitializeD4COption(option: *mut D4COption);
+    pub fn Dio(
+        x: *const f64,
+        x_length: ::std::os::raw::c_int,
+        fs: ::std::os::raw::c_int,
+        option: *const DioOption,
+        temporal_positions: *mut f64,
+        f0: *mut f64,
+    );
+    pub fn InitializeDioOption(option: *mut DioOption);
+    pub fn GetSamplesForDIO(fs: ::std::os::raw::c_int, x_length: ::std::os::raw::c_int, frame_period: f64) -> ::std::os::raw::c_int;
+    pub fn Harvest(
+        x: *const f64,
+        x_length: ::std::os::raw::c_int,
+        fs: ::std::os::raw::c_int,
+        option: *const HarvestOption,
+        temporal_positions: *mut f64,
+        f0: *mut f64,
+    );
+    pub fn InitializeHarvestOption(option: *mut HarvestOption);
+    pub fn GetSamplesForHarvest(fs: ::std::os::raw::c_int, x_length: ::std::os::raw::c_int, frame_period: f64) -> ::std::os::raw::c_int;
+    pub fn fftshift(x: *const f64, x_length: ::std::os::raw::c_int, y: *mut f64);
+    pub fn histc(
+        x: *const f64,
+        x_length: ::std::os::raw::c_int,
+        edges: *const f64,
+        edges_length: ::std::os::raw::c_int,
+        index: *mut ::std::os::raw::c_int,
+    );
+    pub fn interp1(x: *const f64, y: *const f64, x_length: ::std::os::raw::c_int, xi: *const f64, xi_length: ::std::os::raw::c_int, yi: *mut f64);
+    pub fn decimate(x: *const f64, x_length: ::std::os::raw::c_int, r: ::std::os::raw::c_int, y: *mut f64);
+    pub fn matlab_round(x: f64) -> ::std::os::raw::c_int;
+    pub fn diff(x: *const f64, x_length: ::std::os::raw::c_int, y: *mut f64);
+    pub fn interp1Q(
+        x: f64,
+        shift: f64,
+        y: *const f64,
+        x_length: ::std::os::raw::c_int,
+        xi: *const f64,
+        xi_length: ::std::os::raw::c_int,
+        yi: *mut f64,
+    );
+    pub fn randn() -> f64;
+    pub fn randn_reseed();
+    pub fn fast_fftfilt(
+        x: *const f64,
+        x_length: ::std::os::raw::c_int,
+        h: *const f64,
+        h_length: ::std::os::raw::c_int,
+        fft_size: ::std::os::raw::c_int,
+        forward_real_fft: *const ForwardRealFFT,
+        inverse_real_fft: *const InverseRealFFT,
+        y: *mut f64,
+    );
+    pub fn matlab_std(x: *const f64, x_length: ::std::os::raw::c_int) -> f64;
+    pub fn inv(r: *mut *mut f64, n: ::std::os::raw::c_int, invr: *mut *mut f64);
+    pub fn StoneMask(
+        x: *const f64,
+        x_length: ::std::os::raw::c_int,
+        fs: ::std::os::raw::c_int,
+        temporal_positions: *const f64,
+        f0: *const f64,
+        f0_length: ::std::os::raw::c_int,
+        refined_f0: *mut f64,
+    );
+    pub fn Synthesis(
+        f0: *const f64,
+        f0_length: ::std::os::raw::c_int,
+        spectrogram: *const *const f64,
+        aperiodicity: *const *const f64,
+        fft_size: ::std::os::raw::c_int,
+        frame_period: f64,
+        fs: ::std::os::raw::c_int,
+        y_length: ::std::os::raw::c_int,
+        y: *mut f64,
+    );
+    pub fn InitializeSynthesizer(
+        fs: ::std::os::raw::c_int,
+        frame_period: f64,
+        fft_size: ::std::os::raw::c_int,
+        buffer_size: ::std::os::raw::c_int,
+        number_of_pointers: ::std::os::raw::c_int,
+        synth: *mut WorldSynthesizer,
+    );
+    pub fn AddParameters(
+        f0: *mut f64,
+        f0_length: ::std::os::raw::c_int,
+        spectrogram: *mut *mut f64,
+        aperiodicity: *mut *mut f64,
+        synth: *mut WorldSynthesizer,
+    ) -> ::std::os::raw::c_int;
+    pub fn RefreshSynthesizer(synth: *mut WorldSynthesizer);
+    pub fn DestroySynthesizer(synth: *mut WorldSynthesizer);
+    pub fn IsLocked(synth: *mut WorldSynthesizer) -> ::std::os::raw::c_int;
+    pub fn Synthesis2(synth: *mut WorldSynthesizer) -> ::std::os::raw::c_int;
+}
@@ -0,0 +1,275 @@
+//! Replacement of fft.cpp with rustfft and realfft, enabled by the `rustfft` feature.
+//!
+//! The vendored WORLD sources are compiled without fft.cpp, so their FFT calls reach the functions below.
+//! Same as fft.cpp, the results are compatible with FFTW, i.e. the forward transform uses `exp(-2πikn/N)`,
+//! the backward transform uses `exp(2πikn/N)` and neither of them is normalized.
+//! `fft_execute` copies the input and the output, so in-place plans and WORLD's buffers are handled as before.
+//!
+//! original: <https://github.com/mmorise/World/tree/v1.0.0/src/fft.cpp>
+
+use std::cell::RefCell;
+use std::os::raw::{c_int, c_uint};
+use std::ptr;
+use std::slice;
+use std::sync::Arc;
+
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use rustfft::{Fft, FftPlanner};
+
+use crate::{fft_complex, fft_plan, FFT_BACKWARD, FFT_FORWARD};
+
+enum Plan {
+    Complex {
+        fft: Arc<dyn Fft<f64>>,
+        buffer: Vec<Complex<f64>>,
+        scratch: Vec<Complex<f64>>,
+    },
+    RealToComplex {
+        fft: Arc<dyn RealToComplex<f64>>,
+        input: Vec<f64>,
+        output: Vec<Complex<f64>>,
+        scratch: Vec<Complex<f64>>,
+    },
+    ComplexToReal {
+        fft: Arc<dyn ComplexToReal<f64>>,
+        input: Vec<Complex<f64>>,
+        output: Vec<f64>,
+        scratch: Vec<Complex<f64>>,
+    },
+}
+
+thread_local! {
+    // planners cache the twiddle factors, which are shared by the plans of the same size
+    static FFT_PLANNER: RefCell<FftPlanner<f64>> = RefCell::new(FftPlanner::new());
+    static REAL_FFT_PLANNER: RefCell<RealFftPlanner<f64>> = RefCell::new(RealFftPlanner::new());
+}
+
+#[allow(clippy::too_many_arguments)]
+fn new_plan(
+    n: c_int,
+    sign: c_int,
+    flags: c_uint,
+    c_in: *mut fft_complex,
+    in_: *mut f64,
+    c_out: *mut fft_complex,
+    out: *mut f64,
+    plan: Plan,
+) -> fft_plan {
+    fft_plan {
+        n,
+        sign,
+        flags,
+        c_in,
+        in_,
+        c_out,
+        out,
+        input: ptr::null_mut(),
+        ip: ptr::null_mut(),
+        // `w` holds the twiddle factors in fft.cpp, and holds the boxed plan here
+        w: Box::into_raw(Box::new(plan)).cast(),
+    }
+}
+
+fn to_complex(value: &fft_complex) -> Complex<f64> {
+    Complex::new(value[0], value[1])
+}
+
+fn from_complex(value: &Complex<f64>) -> fft_complex {
+    [value.re, value.im]
+}
+
+#[no_mangle]
+pub extern "C" fn fft_plan_dft_1d(n: c_int, in_: *mut fft_complex, out: *mut fft_complex, sign: c_int, flags: c_uint) -> fft_plan {
+    let fft = FFT_PLANNER.with(|planner| {
+        let mut planner = planner.borrow_mut();
+        if sign == FFT_FORWARD as c_int {
+            planner.plan_fft_forward(n as usize)
+        } else {
+            planner.plan_fft_inverse(n as usize)
+        }
+    });
+    let plan = Plan::Complex {
+        buffer: vec![Complex::default(); n as usize],
+        scratch: vec![Complex::default(); fft.get_inplace_scratch_len()],
+        fft,
+    };
+    new_plan(n, sign, flags, in_, ptr::null_mut(), out, ptr::null_mut(), plan)
+}
+
+#[no_mangle]
+pub extern "C" fn fft_plan_dft_c2r_1d(n: c_int, in_: *mut fft_complex, out: *mut f64, flags: c_uint) -> fft_plan {
+    let fft = REAL_FFT_PLANNER.with(|planner| planner.borrow_mut().plan_fft_inverse(n as usize));
+    let plan = Plan::ComplexToReal {
+        input: fft.make_input_vec(),
+        output: fft.make_output_vec(),
+        scratch: fft.make_scratch_vec(),
+        fft,
+    };
+    new_plan(n, FFT_BACKWARD as c_int, flags, in_, ptr::null_mut(), ptr::null_mut(), out, plan)
+}
+
+#[no_mangle]
+pub extern "C" fn fft_plan_dft_r2c_1d(n: c_int, in_: *mut f64, out: *mut fft_complex, flags: c_uint) -> fft_plan {
+    let fft = REAL_FFT_PLANNER.with(|planner| planner.borrow_mut().plan_fft_forward(n as usize));
+    let plan = Plan::RealToComplex {
+        input: fft.make_input_vec(),
+        output: fft.make_output_vec(),
+        scratch: fft.make_scratch_vec(),
+        fft,
+    };
+    new_plan(n, FFT_FORWARD as c_int, flags, ptr::null_mut(), in_, out, ptr::null_mut(), plan)
+}
+
+/// # Safety
+/// `p` must be created by the functions above and not destroyed, and its buffers must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn fft_execute(p: fft_plan) {
+    let Some(plan) = p.w.cast::<Plan>().as_mut() else {
+        return;
+    };
+    let n = p.n as usize;
+    match plan {
+        Plan::Complex { fft, buffer, scratch } => {
+            for (buffer, input) in buffer.iter_mut().zip(slice::from_raw_parts(p.c_in, n)) {
+                *buffer = to_complex(input);
+            }
+            fft.process_with_scratch(buffer, scratch);
+            for (output, buffer) in slice::from_raw_parts_mut(p.c_out, n).iter_mut().zip(buffer.iter()) {
+                *output = from_complex(buffer);
+            }
+        }
+        Plan::RealToComplex { fft, input, output, scratch } => {
+            input.copy_from_slice(slice::from_raw_parts(p.in_, n));
+            fft.process_with_scratch(input, output, scratch).unwrap();
+            for (c_out, output) in slice::from_raw_parts_mut(p.c_out, output.len()).iter_mut().zip(output.iter()) {
+                *c_out = from_complex(output);
+            }
+        }
+        Plan::ComplexToReal { fft, input, output, scratch } => {
+            for (input, c_in) in input.iter_mut().zip(slice::from_raw_parts(p.c_in, n / 2 + 1)) {
+                *input = to_complex(c_in);
+            }
+            // FFTW and fft.cpp ignore the imaginary parts of DC and Nyquist, while realfft rejects nonzero values
+            input[0].im = 0.;
+            if n.is_multiple_of(2) {
+                input[n / 2].im = 0.;
+            }
+            fft.process_with_scratch(input, output, scratch).unwrap();
+            slice::from_raw_parts_mut(p.out, n).copy_from_slice(output);
+        }
+    }
+}
+
+/// # Safety
+/// `p` must be created by the functions above and not destroyed yet.
+#[no_mangle]
+pub unsafe extern "C" fn fft_destroy_plan(p: fft_plan) {
+    if !p.w.is_null() {
+        drop(Box::from_raw(p.w.cast::<Plan>()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_randn_state, world_sys_randn, RandnState};
+
+    // fft.cpp compiled with renamed symbols, see build.rs
+    extern "C" {
+        fn world_sys_ooura_fft_plan_dft_1d(n: c_int, in_: *mut fft_complex, out: *mut fft_complex, sign: c_int, flags: c_uint) -> fft_plan;
+        fn world_sys_ooura_fft_plan_dft_c2r_1d(n: c_int, in_: *mut fft_complex, out: *mut f64, flags: c_uint) -> fft_plan;
+        fn world_sys_ooura_fft_plan_dft_r2c_1d(n: c_int, in_: *mut f64, out: *mut fft_complex, flags: c_uint) -> fft_plan;
+        fn world_sys_ooura_fft_execute(p: fft_plan);
+        fn world_sys_ooura_fft_destroy_plan(p: fft_plan);
+    }
+
+    const FFT_SIZES: &[usize] = &[4, 16, 1024, 2048, 8192];
+    const TOLERANCE: f64 = 1e-10;
+
+    fn random(len: usize) -> Vec<f64> {
+        with_randn_state(&mut RandnState::from_seed(len as u64), || (0..len).map(|_| world_sys_randn()).collect())
+    }
+
+    fn assert_close(expected: &[f64], actual: &[f64]) {
+        let scale = expected.iter().fold(1f64, |max, x| max.max(x.abs()));
+        for (i, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+            assert!((expected - actual).abs() <= TOLERANCE * scale, "{i}: {expected} != {actual}");
+        }
+    }
+
+    /// Execute a plan of this backend and of fft.cpp on copies of the same buffers, and compare the outputs.
+    unsafe fn compare<I: Clone, O: Default + Clone>(
+        input: Vec<I>,
+        output_len: usize,
+        plan: impl Fn(*mut I, *mut O) -> fft_plan,
+        ooura_plan: impl Fn(*mut I, *mut O) -> fft_plan,
+        flatten: impl Fn(&[O]) -> Vec<f64>,
+    ) {
+        let (mut input1, mut input2) = (input.clone(), input);
+        let (mut output1, mut output2) = (vec![O::default(); output_len], vec![O::default(); output_len]);
+        let p = plan(input1.as_mut_ptr(), output1.as_mut_ptr());
+        fft_execute(ptr::read(&p));
+        fft_destroy_plan(p);
+        let p = ooura_plan(input2.as_mut_ptr(), output2.as_mut_ptr());
+        world_sys_ooura_fft_execute(ptr::read(&p));
+        world_sys_ooura_fft_destroy_plan(p);
+        assert_close(&flatten(&output2), &flatten(&output1));
+    }
+
+    fn flatten_complex(values: &[fft_complex]) -> Vec<f64> {
+        values.iter().flatten().copied().collect()
+    }
+
+    #[test]
+    fn test_compare_with_ooura() {
+        for &n in FFT_SIZES {
+            let real = random(n);
+            let complex = random(2 * n).chunks(2).map(|c| [c[0], c[1]]).collect::<Vec<fft_complex>>();
+            let half_complex = complex[..n / 2 + 1].to_vec();
+            let size = n as c_int;
+            unsafe {
+                compare(
+                    real,
+                    n / 2 + 1,
+                    |i, o| fft_plan_dft_r2c_1d(size, i, o, 0),
+                    |i, o| world_sys_ooura_fft_plan_dft_r2c_1d(size, i, o, 0),
+                    flatten_complex,
+                );
+                compare(
+                    half_complex,
+                    n,
+                    |i, o| fft_plan_dft_c2r_1d(size, i, o, 0),
+                    |i, o| world_sys_ooura_fft_plan_dft_c2r_1d(size, i, o, 0),
+                    <[f64]>::to_vec,
+                );
+                for sign in [FFT_FORWARD, FFT_BACKWARD] {
+                    compare(
+                        complex.clone(),
+                        n,
+                        |i, o| fft_plan_dft_1d(size, i, o, sign as c_int, 0),
+                        |i, o| world_sys_ooura_fft_plan_dft_1d(size, i, o, sign as c_int, 0),
+                        flatten_complex,
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_in_place() {
+        let n = 64;
+        let mut buffer = random(2 * n).chunks(2).map(|c| [c[0], c[1]]).collect::<Vec<fft_complex>>();
+        let original = buffer.clone();
+        unsafe {
+            let forward = fft_plan_dft_1d(n as c_int, buffer.as_mut_ptr(), buffer.as_mut_ptr(), FFT_FORWARD as c_int, 0);
+            let backward = fft_plan_dft_1d(n as c_int, buffer.as_mut_ptr(), buffer.as_mut_ptr(), FFT_BACKWARD as c_int, 0);
+            fft_execute(ptr::read(&forward));
+            fft_execute(ptr::read(&backward));
+            fft_destroy_plan(forward);
+            fft_destroy_plan(backward);
+        }
+        let restored = buffer.iter().map(|[re, im]| [re / n as f64, im / n as f64]).collect::<Vec<_>>();
+        assert_close(&flatten_complex(&original), &flatten_complex(&restored));
+    }
+}
@@ -0,0 +1,118 @@
+//! Layout checks of the structs of WORLD, which are laid out as the C headers on 64-bit targets.
+//!
+//! They cover the pre-generated src/bindings.rs as well as the bindings generated by the `bindgen` feature.
+
+use crate::*;
+use std::mem::{align_of, offset_of, size_of};
+
+macro_rules! assert_layout {
+    ($name:ident, size: $size:expr, align: $align:expr, { $($field:ident: $offset:expr),* $(,)? }) => {
+        const _: () = {
+            assert!(size_of::<$name>() == $size);
+            assert!(align_of::<$name>() == $align);
+            $(assert!(offset_of!($name, $field) == $offset);)*
+        };
+    };
+}
+
+assert_layout!(fft_plan, size: 72, align: 8, {
+    n: 0,
+    sign: 4,
+    flags: 8,
+    c_in: 16,
+    in_: 24,
+    c_out: 32,
+    out: 40,
+    input: 48,
+    ip: 56,
+    w: 64,
+});
+
+assert_layout!(ForwardRealFFT, size: 96, align: 8, {
+    fft_size: 0,
+    waveform: 8,
+    spectrum: 16,
+    forward_fft: 24,
+});
+
+assert_layout!(InverseRealFFT, size: 96, align: 8, {
+    fft_size: 0,
+    waveform: 8,
+    spectrum: 16,
+    inverse_fft: 24,
+});
+
+assert_layout!(InverseComplexFFT, size: 96, align: 8, {
+    fft_size: 0,
+    input: 8,
+    output: 16,
+    inverse_fft: 24,
+});
+
+assert_layout!(MinimumPhaseAnalysis, size: 176, align: 8, {
+    fft_size: 0,
+    log_spectrum: 8,
+    minimum_phase_spectrum: 16,
+    cepstrum: 24,
+    inverse_fft: 32,
+    forward_fft: 104,
+});
+
+assert_layout!(CheapTrickOption, size: 24, align: 8, {
+    q1: 0,
+    f0_floor: 8,
+    fft_size: 16,
+});
+
+assert_layout!(D4COption, size: 8, align: 8, {
+    threshold: 0,
+});
+
+assert_layout!(DioOption, size: 48, align: 8, {
+    f0_floor: 0,
+    f0_ceil: 8,
+    channels_in_octave: 16,
+    frame_period: 24,
+    speed: 32,
+    allowed_range: 40,
+});
+
+assert_layout!(HarvestOption, size: 24, align: 8, {
+    f0_floor: 0,
+    f0_ceil: 8,
+    frame_period: 16,
+});
+
+assert_layout!(WorldSynthesizer, size: 552, align: 8, {
+    fs: 0,
+    frame_period: 8,
+    buffer_size: 16,
+    number_of_pointers: 20,
+    fft_size: 24,
+    buffer: 32,
+    current_pointer: 40,
+    i: 44,
+    dc_remover: 48,
+    f0_length: 56,
+    f0_origin: 64,
+    spectrogram: 72,
+    aperiodicity: 80,
+    current_pointer2: 88,
+    head_pointer: 92,
+    synthesized_sample: 96,
+    handoff: 100,
+    handoff_phase: 104,
+    handoff_f0: 112,
+    last_location: 120,
+    cumulative_frame: 124,
+    current_frame: 128,
+    interpolated_vuv: 136,
+    pulse_locations: 144,
+    pulse_locations_index: 152,
+    pulse_locations_time_shift: 160,
+    number_of_pulses: 168,
+    impulse_response: 176,
+    minimum_phase: 184,
+    inverse_real_fft: 360,
+    forward_real_fft: 456,
+});
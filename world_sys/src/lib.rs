@@ -1,4 +1,17 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
+#[cfg(feature = "bindgen")]
 include!(concat!(env!("OUT_DIR"), "/bindgen.rs"));
+// Pre-generated bindings of the WORLD v1.0.0 headers, used when the `bindgen` feature is disabled.
+// Regenerate them with `WORLD_SYS_UPDATE_BINDINGS=1 cargo build -p world_sys` after updating WORLD.
+#[cfg(not(feature = "bindgen"))]
+include!("bindings.rs");
+
+#[cfg(all(feature = "rustfft", not(feature = "system")))]
+mod fft;
+#[cfg(target_pointer_width = "64")]
+mod layout;
+mod randn;
+
+pub use randn::{with_randn_state, world_sys_randn, RandnState, RANDN_REPLACED};
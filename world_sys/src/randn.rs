@@ -0,0 +1,130 @@
+//! Replacement of `randn` in matlabfunctions.cpp.
+//!
+//! The vendored WORLD sources are compiled so that their `randn` calls reach `world_sys_randn` instead.
+//! The generator keeps its state per thread, so parallel analyses and syntheses never race on it,
+//! and the state can be seeded to get reproducible output.
+//! With the `system` feature, the linked WORLD keeps its own `randn` and [`with_randn_state`] has no effect on it,
+//! which [`RANDN_REPLACED`] tells at run time.
+//!
+//! original: <https://github.com/mmorise/World/tree/v1.0.0/src/matlabfunctions.cpp>
+
+use std::cell::Cell;
+
+/// Whether the linked WORLD calls the `randn` of this module in place of its own, which is false with the `system` feature.
+pub const RANDN_REPLACED: bool = !cfg!(feature = "system");
+
+/// State of the xorshift generator used by `randn`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RandnState([u32; 4]);
+
+impl RandnState {
+    /// Same state as `randn_reseed` sets.
+    pub const INITIAL: RandnState = RandnState([123456789, 362436069, 521288629, 88675123]);
+
+    pub fn from_seed(seed: u64) -> RandnState {
+        let mut seed = seed;
+        let mut next = || {
+            // splitmix64
+            seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        let (a, b) = (next(), next());
+        let state = [a as u32, (a >> 32) as u32, b as u32, (b >> 32) as u32];
+        if state == [0; 4] {
+            RandnState::INITIAL
+        } else {
+            RandnState(state)
+        }
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        let [x, y, z, w] = self.0;
+        let t = x ^ (x << 11);
+        let next = (w ^ (w >> 19)) ^ (t ^ (t >> 8));
+        self.0 = [y, z, w, next];
+        next
+    }
+
+    fn randn(&mut self) -> f64 {
+        let tmp = (0..12).map(|_| self.next_u32() >> 4).fold(0u32, u32::wrapping_add);
+        tmp as f64 / 268435456.0 - 6.0
+    }
+}
+
+impl Default for RandnState {
+    fn default() -> Self {
+        RandnState::INITIAL
+    }
+}
+
+thread_local! {
+    static STATE: Cell<RandnState> = const { Cell::new(RandnState::INITIAL) };
+}
+
+/// Stores the advanced state back and restores the previous state of the thread on drop, even if `f` panics.
+struct StateGuard<'a> {
+    state: &'a mut RandnState,
+    previous: RandnState,
+}
+
+impl Drop for StateGuard<'_> {
+    fn drop(&mut self) {
+        *self.state = STATE.with(|current| current.replace(self.previous));
+    }
+}
+
+/// Run `f` with `state` as the `randn` state of the current thread, then store the advanced state back to `state`.
+pub fn with_randn_state<R>(state: &mut RandnState, f: impl FnOnce() -> R) -> R {
+    let previous = STATE.with(|current| current.replace(*state));
+    let _guard = StateGuard { state, previous };
+    f()
+}
+
+/// Pseudorandom number of approximately standard normal distribution, called from WORLD in place of `randn`.
+#[doc(hidden)]
+#[no_mangle]
+pub extern "C" fn world_sys_randn() -> f64 {
+    STATE.with(|current| {
+        let mut state = current.get();
+        let value = state.randn();
+        current.set(state);
+        value
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_randn_state() {
+        let draw = |state: &mut RandnState| with_randn_state(state, || (0..16).map(|_| world_sys_randn()).collect::<Vec<_>>());
+        let mut state1 = RandnState::from_seed(42);
+        let mut state2 = RandnState::from_seed(42);
+        let first = draw(&mut state1);
+        assert_eq!(first, draw(&mut state2));
+        assert_eq!(state1, state2);
+        assert_ne!(first, draw(&mut state1));
+        assert_ne!(draw(&mut RandnState::from_seed(1)), draw(&mut RandnState::from_seed(2)));
+
+        let samples = draw(&mut RandnState::default());
+        assert!(samples.iter().all(|x| (-6.0..6.0).contains(x)));
+    }
+
+    #[test]
+    fn test_with_randn_state_restores_on_panic() {
+        let mut state = RandnState::from_seed(42);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            with_randn_state(&mut state, || {
+                world_sys_randn();
+                panic!("in the closure");
+            })
+        }));
+        assert!(result.is_err());
+        assert_ne!(state, RandnState::from_seed(42));
+        assert_eq!(STATE.with(Cell::get), RandnState::INITIAL);
+    }
+}